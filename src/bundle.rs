@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use e_drone::file::EncryptedBinary;
+
+use crate::firmware::{self, format_version};
+
+
+// 펌웨어 묶음 파일(tar)
+//   manifest.toml       각 펌웨어의 정보
+//   <펌웨어 파일>...
+//
+// manifest.toml
+//   [[firmware]]
+//   file = "drone_p5.eb"
+//   model_number = "Drone3DroneP5"
+//   version = "22.2.3"
+//   sha256 = "..."
+//   min_bootloader = "1.0.0"
+//   release_notes = "..."
+//   signature = "..."         (선택, 파일 전체의 ed25519 서명)
+pub const NAME_MANIFEST: &str = "manifest.toml";
+
+
+// tar 헤더의 식별 문자열 위치
+const INDEX_TAR_MAGIC: usize = 257;
+const TAR_MAGIC: &[u8] = b"ustar";
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry
+{
+    pub file: String,                       // 묶음 안의 파일 이름
+    pub model_number: String,               // ModelNumber 이름(예: Drone3DroneP5)
+    pub version: String,                    // major.minor.build
+    pub sha256: String,                     // 파일 전체의 SHA-256 값(16진수)
+
+    #[serde(default)]
+    pub min_bootloader: Option<String>,     // 필요한 최소 부트로더 버전(표시용)
+
+    #[serde(default)]
+    pub release_notes: String,
+
+    #[serde(default)]
+    pub signature: Option<String>,          // 파일 전체의 ed25519 서명(16진수)
+}
+
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest
+{
+    #[serde(default)]
+    pub firmware: Vec<ManifestEntry>,
+}
+
+
+pub struct BundleImage
+{
+    pub entry: ManifestEntry,
+    pub eb: EncryptedBinary,
+}
+
+
+// 파일 앞부분의 tar 헤더로 묶음 파일인지 확인
+pub fn is_bundle(path: &Path) -> bool
+{
+    let mut buffer = [0u8; INDEX_TAR_MAGIC + 5];
+
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut buffer))
+        .map(|_| &buffer[INDEX_TAR_MAGIC..] == TAR_MAGIC)
+        .unwrap_or(false)
+}
+
+
+// 묶음 파일 안의 펌웨어를 모두 읽음
+// 목록에 있는 파일이 없거나 목록의 정보와 다르면 묶음 전체를 사용하지 않음
+pub fn read_bundle(path: &Path) -> Result<Vec<BundleImage>, String>
+{
+    let mut map_file = HashMap::new();
+
+    let mut archive = tar::Archive::new(File::open(path).map_err(|e| e.to_string())?);
+    for entry in archive.entries().map_err(|e| e.to_string())?
+    {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let name = entry.path().map_err(|e| e.to_string())?.to_string_lossy().to_string();
+
+        let mut data_array = Vec::new();
+        entry.read_to_end(&mut data_array).map_err(|e| e.to_string())?;
+        map_file.insert(name, data_array);
+    }
+
+    let text = map_file
+        .get(NAME_MANIFEST)
+        .ok_or_else(|| format!("{} is missing", NAME_MANIFEST))?;
+    let manifest = parse_manifest(text)?;
+
+    manifest.firmware
+        .into_iter()
+        .map(|entry|
+        {
+            let data_array = map_file
+                .remove(&entry.file)
+                .ok_or_else(|| format!("{} is missing", entry.file))?;
+
+            let name_file = path.join(&entry.file).to_string_lossy().to_string();
+            let eb = firmware::parse_firmware_file(&name_file, data_array).map_err(|e| format!("{}: {}", entry.file, e))?;
+
+            check_entry(&entry, &eb).map_err(|e| format!("{}: {}", entry.file, e))?;
+
+            Ok(BundleImage{ entry, eb })
+        })
+        .collect()
+}
+
+
+// 묶음 파일 안에 있는 펌웨어 파일의 목록 정보(다른 펌웨어 파일은 읽지 않음)
+pub fn read_manifest_entry(path: &Path, file: &str) -> Result<ManifestEntry, String>
+{
+    let mut archive = tar::Archive::new(File::open(path).map_err(|e| e.to_string())?);
+    for entry in archive.entries().map_err(|e| e.to_string())?
+    {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        if entry.path().map_err(|e| e.to_string())?.as_os_str() != NAME_MANIFEST
+        {
+            continue;
+        }
+
+        let mut text = Vec::new();
+        entry.read_to_end(&mut text).map_err(|e| e.to_string())?;
+
+        return parse_manifest(&text)?
+            .firmware
+            .into_iter()
+            .find(|entry| entry.file == file)
+            .ok_or_else(|| format!("{} is missing", file));
+    }
+
+    Err(format!("{} is missing", NAME_MANIFEST))
+}
+
+
+fn parse_manifest(text: &[u8]) -> Result<Manifest, String>
+{
+    let manifest: Manifest = toml::from_slice(text).map_err(|e| format!("{}: {}", NAME_MANIFEST, e))?;
+
+    if manifest.firmware.is_empty()
+    {
+        return Err(format!("{} lists no firmware", NAME_MANIFEST));
+    }
+
+    Ok(manifest)
+}
+
+
+// 목록의 정보와 펌웨어 헤더, 파일 내용 비교
+fn check_entry(entry: &ManifestEntry, eb: &EncryptedBinary) -> Result<(), String>
+{
+    let model_number = format!("{:?}", eb.header.model_number);
+    if entry.model_number != model_number
+    {
+        return Err(format!("Model number is {} in the manifest but {} in the file", entry.model_number, model_number));
+    }
+
+    let version = format_version(&eb.header.version);
+    if entry.version != version
+    {
+        return Err(format!("Version is {} in the manifest but {} in the file", entry.version, version));
+    }
+
+    if !entry.sha256.eq_ignore_ascii_case(&firmware::hash_firmware(eb))
+    {
+        return Err(String::from("Checksum mismatch"));
+    }
+
+    Ok(())
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use std::fs;
+
+    use e_drone::system::{ModelNumber, Version};
+
+    use crate::emulator;
+
+    #[test]
+    fn bundle_images_match_manifest()
+    {
+        let path = std::env::temp_dir().join(format!("drone_updater_bundle_{}.tar", std::process::id()));
+
+        let eb_drone = emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 3 }, 16 * 10);
+        let eb_controller = emulator::build_firmware(ModelNumber::Drone3ControllerP2, Version{ major: 21, minor: 1, build: 0 }, 16 * 20);
+
+        let entry_drone = ManifestEntry
+        {
+            file: String::from("drone.eb"),
+            model_number: String::from("Drone3DroneP5"),
+            version: String::from("22.2.3"),
+            sha256: firmware::hash_firmware(&eb_drone),
+            min_bootloader: Some(String::from("1.0.0")),
+            release_notes: String::from("Hover stability"),
+            signature: None,
+        };
+        let mut entry_controller = ManifestEntry
+        {
+            file: String::from("controller.eb"),
+            model_number: String::from("Drone3ControllerP2"),
+            version: String::from("21.1.0"),
+            sha256: firmware::hash_firmware(&eb_controller),
+            min_bootloader: None,
+            release_notes: String::new(),
+            signature: None,
+        };
+
+        emulator::write_bundle(&path, &[(&entry_drone, &eb_drone.data_array), (&entry_controller, &eb_controller.data_array)]);
+        assert!(is_bundle(&path));
+        let vec_image = read_bundle(&path).unwrap();
+
+        // 목록과 다른 파일이 들어 있으면 묶음 전체를 거부
+        entry_controller.sha256 = firmware::hash_firmware(&eb_drone);
+        emulator::write_bundle(&path, &[(&entry_drone, &eb_drone.data_array), (&entry_controller, &eb_controller.data_array)]);
+        let error = read_bundle(&path).map(|_| ()).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(vec_image.len(), 2);
+        assert_eq!(vec_image[0].entry, entry_drone);
+        assert_eq!(vec_image[0].eb.data_array, eb_drone.data_array);
+        assert_eq!(vec_image[1].eb.header.model_number, ModelNumber::Drone3ControllerP2);
+        assert_eq!(error, "controller.eb: Checksum mismatch");
+    }
+}
@@ -0,0 +1,353 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, LineWriter, Write},
+    path::Path,
+    time::Instant,
+};
+
+use crate::trace::format_hex;
+use crate::transport::Transport;
+
+
+// 통신 기록 파일 형식
+//   # drone_updater capture 1
+//   # port COM3
+//   <경과 시간(us)> TX|RX <16진수 데이터>
+const HEADER_CAPTURE: &str = "# drone_updater capture 1";
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction
+{
+    Tx,     // 장치로 전송
+    Rx,     // 장치로부터 수신
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureRecord
+{
+    pub time_us: u64,           // 기록 시작 후 경과 시간
+    pub direction: Direction,
+    pub vec_data: Vec<u8>,
+}
+
+
+// -- Capture ---------------------------------------------------------------------------------------------------
+#[derive(Debug, Clone, Default)]
+pub struct Capture
+{
+    pub port: String,
+    pub vec_record: Vec<CaptureRecord>,
+}
+
+
+impl Capture
+{
+    pub fn read(path: &Path) -> Result<Capture, String>
+    {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Capture::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+
+    pub fn parse(text: &str) -> Result<Capture, String>
+    {
+        let mut capture = Capture::default();
+
+        for (index_line, line) in text.lines().enumerate()
+        {
+            let line = line.trim();
+
+            if let Some(port) = line.strip_prefix("# port ")
+            {
+                capture.port = port.to_string();
+                continue;
+            }
+
+            if line.is_empty() || line.starts_with('#')
+            {
+                continue;
+            }
+
+            capture.vec_record.push(Capture::parse_record(line).map_err(|e| format!("line {}: {}", index_line + 1, e))?);
+        }
+
+        Ok(capture)
+    }
+
+
+    fn parse_record(line: &str) -> Result<CaptureRecord, String>
+    {
+        let mut iter = line.split_whitespace();
+
+        let time_us = iter.next()
+            .and_then(|text| text.parse::<u64>().ok())
+            .ok_or("Invalid time")?;
+
+        let direction = match iter.next()
+        {
+            Some("TX") => Direction::Tx,
+            Some("RX") => Direction::Rx,
+            _ => return Err(String::from("Invalid direction")),
+        };
+
+        let vec_data = iter
+            .map(|text| u8::from_str_radix(text, 16).map_err(|_| format!("Invalid byte '{}'", text)))
+            .collect::<Result<Vec<u8>, String>>()?;
+
+        Ok(CaptureRecord{ time_us, direction, vec_data })
+    }
+
+
+    pub fn count(&self, direction: Direction) -> usize
+    {
+        self.vec_record.iter().filter(|record| record.direction == direction).count()
+    }
+}
+
+
+// -- RecordingTransport ----------------------------------------------------------------------------------------
+// 다른 통로로 주고 받는 모든 데이터를 기록 파일에 저장
+pub struct RecordingTransport
+{
+    transport: Box<dyn Transport>,
+    writer: Box<dyn Write + Send>,
+    time_start: Instant,
+}
+
+
+impl RecordingTransport
+{
+    pub fn new(transport: Box<dyn Transport>, mut writer: Box<dyn Write + Send>) -> RecordingTransport
+    {
+        let _ = writeln!(writer, "{}", HEADER_CAPTURE);
+        let _ = writeln!(writer, "# port {}", transport.describe());
+
+        RecordingTransport
+        {
+            transport,
+            writer,
+            time_start: Instant::now(),
+        }
+    }
+
+
+    // 기록에 실패해도 통신은 계속 진행
+    fn record(&mut self, direction: Direction, slice_data: &[u8])
+    {
+        let _ = writeln!(self.writer, "{} {} {}",
+            self.time_start.elapsed().as_micros(),
+            match direction { Direction::Tx => "TX", Direction::Rx => "RX" },
+            format_hex(slice_data));
+    }
+}
+
+
+impl Transport for RecordingTransport
+{
+    fn write(&mut self, slice_data: &[u8]) -> io::Result<usize>
+    {
+        self.record(Direction::Tx, slice_data);
+        self.transport.write(slice_data)
+    }
+
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize>
+    {
+        let length_read = self.transport.read(buffer)?;
+
+        if length_read > 0
+        {
+            self.record(Direction::Rx, &buffer[..length_read]);
+        }
+
+        Ok(length_read)
+    }
+
+
+    fn close(&mut self)
+    {
+        let _ = self.writer.flush();
+        self.transport.close();
+    }
+
+
+    fn describe(&self) -> String
+    {
+        self.transport.describe()
+    }
+}
+
+
+// 기록 파일 생성(폴더가 없으면 함께 생성)
+pub fn create_file(path: &Path) -> io::Result<Box<dyn Write + Send>>
+{
+    if let Some(path_dir) = path.parent()
+    {
+        fs::create_dir_all(path_dir)?;
+    }
+
+    Ok(Box::new(LineWriter::new(File::create(path)?)))
+}
+
+
+// -- ReplayTransport -------------------------------------------------------------------------------------------
+// 기록 파일의 수신 데이터를 다시 전달
+// 기록 당시 수신 데이터 앞에 있던 전송 횟수만큼 Updater가 전송해야 다음 수신 데이터를 내보냄
+pub struct ReplayTransport
+{
+    port: String,
+    queue_rx: VecDeque<(usize, Vec<u8>)>,   // (앞선 전송 횟수, 수신 데이터)
+    vec_tx: Vec<Vec<u8>>,                   // 기록된 전송 데이터(비교용)
+    count_tx: usize,                        // Updater가 전송한 횟수
+    count_mismatch: usize,                  // 기록과 다른 전송 데이터 수
+    index_data: usize,                      // 일부만 전달한 수신 데이터의 다음 위치
+}
+
+
+impl ReplayTransport
+{
+    pub fn new(capture: &Capture) -> ReplayTransport
+    {
+        let mut queue_rx = VecDeque::new();
+        let mut vec_tx = Vec::new();
+
+        for record in capture.vec_record.iter()
+        {
+            match record.direction
+            {
+                Direction::Tx => vec_tx.push(record.vec_data.clone()),
+                Direction::Rx => queue_rx.push_back((vec_tx.len(), record.vec_data.clone())),
+            }
+        }
+
+        ReplayTransport
+        {
+            port: capture.port.clone(),
+            queue_rx,
+            vec_tx,
+            count_tx: 0,
+            count_mismatch: 0,
+            index_data: 0,
+        }
+    }
+
+
+    pub fn get_count_mismatch(&self) -> usize
+    {
+        self.count_mismatch
+    }
+
+
+    // 아직 내보내지 않은 수신 데이터 수
+    pub fn get_count_rx_left(&self) -> usize
+    {
+        self.queue_rx.len()
+    }
+}
+
+
+impl Transport for ReplayTransport
+{
+    fn write(&mut self, slice_data: &[u8]) -> io::Result<usize>
+    {
+        if self.vec_tx.get(self.count_tx).map(|vec_data| &vec_data[..]) != Some(slice_data)
+        {
+            self.count_mismatch += 1;
+        }
+
+        self.count_tx += 1;
+
+        Ok(slice_data.len())
+    }
+
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize>
+    {
+        let (count_tx_before, vec_data) = match self.queue_rx.front()
+        {
+            Some(front) => front,
+            None => return Ok(0),
+        };
+
+        if *count_tx_before > self.count_tx
+        {
+            return Ok(0);
+        }
+
+        let length = (vec_data.len() - self.index_data).min(buffer.len());
+        buffer[..length].copy_from_slice(&vec_data[self.index_data..self.index_data + length]);
+        self.index_data += length;
+
+        if self.index_data >= vec_data.len()
+        {
+            self.queue_rx.pop_front();
+            self.index_data = 0;
+        }
+
+        Ok(length)
+    }
+
+
+    fn close(&mut self)
+    {
+    }
+
+
+    fn describe(&self) -> String
+    {
+        format!("replay:{}", self.port)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use std::{sync::Arc, time::Duration};
+
+    use e_drone::system::{DeviceType, ModeUpdate, ModelNumber, Version};
+
+    use crate::emulator;
+    use crate::updater::{Sequence, Updater};
+
+    #[test]
+    fn recorded_session_replays_to_same_result()
+    {
+        let path_dir = std::env::temp_dir().join(format!("drone_updater_capture_{}", std::process::id()));
+        let vec_file = Arc::new(vec![emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 0 }, 16 * 30)]);
+
+        // 가상 장치와의 업데이트 과정을 기록
+        let mut config = emulator::config_unverified();
+        config.path_capture = Some(path_dir.clone());
+
+        let (mut updater, mut emulator) = emulator::connect(config, vec_file.clone(), DeviceType::Drone, ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 1, build: 0 }, ModeUpdate::Ready);
+        assert_eq!(emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10)), Sequence::UpdateComplete);
+        drop(updater);
+
+        let path = fs::read_dir(&path_dir).unwrap().next().unwrap().unwrap().path();
+        let capture = Capture::read(&path).unwrap();
+        fs::remove_dir_all(&path_dir).unwrap();
+
+        assert_eq!(capture.port, "channel-a");
+        assert!(capture.count(Direction::Tx) > 0 && capture.count(Direction::Rx) > 0);
+
+        // 기록만으로 같은 결과를 재현
+        let mut updater = Updater::with_firmware(emulator::config_unverified(), vec_file);
+        updater.connect(Box::new(ReplayTransport::new(&capture)));
+
+        let time_start = Instant::now();
+        while !updater.get_sequence().is_finished() && time_start.elapsed() < Duration::from_secs(10)
+        {
+            updater.run();
+            std::thread::sleep(Duration::from_micros(200));
+        }
+
+        assert_eq!(*updater.get_sequence(), Sequence::UpdateComplete);
+    }
+}
@@ -0,0 +1,320 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use e_drone::system::DeviceType;
+
+
+// 업데이트할 장치를 찾는 방법
+#[derive(Debug, Clone, PartialEq)]
+pub enum Discovery
+{
+    Hotplug,            // 시작 후 새로 연결되는 포트를 사용
+    Port(String),       // 지정된 포트를 사용
+    ScanAll,            // 연결되어 있는 모든 포트에 차례로 장치 정보를 요청
+}
+
+
+// 요청 주기, 응답 대기 시간과 재시도 한도(무선 연결처럼 느린 통로에서는 더 길게 설정)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy
+{
+    pub time_interval_ms: u32,                  // 요청 및 재전송 주기
+    pub time_information_timeout_ms: u32,       // 장치 정보 응답 대기 시간(모든 장치 종류에 한 번씩 요청하도록 최소 9 × time_interval_ms, 기본값으로는 1800 ms)
+    pub time_location_timeout_ms: u32,          // 업데이트 위치 응답 대기 시간
+    pub count_error_max: u32,                   // 연속 재전송 한도
+    pub time_update_limit_ms: u32,              // 펌웨어 전송 제한 시간
+    pub time_verify_limit_ms: u32,              // 업데이트 후 장치가 다시 응답할 때까지 기다리는 시간
+    pub time_reboot_limit_ms: u32,              // 부트로더로 다시 시작한 장치가 응답할 때까지 기다리는 시간
+}
+
+
+impl RetryPolicy
+{
+    pub fn new() -> RetryPolicy
+    {
+        RetryPolicy
+        {
+            time_interval_ms: 200,
+            time_information_timeout_ms: 1200,
+            time_location_timeout_ms: 1200,
+            count_error_max: 30,
+            time_update_limit_ms: 300000,
+            time_verify_limit_ms: 10000,
+            time_reboot_limit_ms: 10000,
+        }
+    }
+}
+
+
+impl Default for RetryPolicy
+{
+    fn default() -> Self
+    {
+        RetryPolicy::new()
+    }
+}
+
+
+// 펌웨어 전송 시 한 패킷에 담을 블럭 수(16 byte 단위)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PacketPolicy
+{
+    pub count_block: u16,       // 처음 사용할 블럭 수(적응 모드가 아니면 계속 사용)
+    pub count_block_max: u16,   // 적응 모드에서 늘릴 수 있는 최대 블럭 수(최대 15)
+    pub flag_adaptive: bool,    // 통신 상태에 따라 블럭 수 조절
+    pub count_window: u16,      // 응답을 기다리지 않고 연속으로 보낼 패킷 수(1이면 하나씩 보내고 응답 확인)
+}
+
+
+impl PacketPolicy
+{
+    pub fn new() -> PacketPolicy
+    {
+        PacketPolicy
+        {
+            count_block: 2,
+            count_block_max: 15,
+            flag_adaptive: false,
+            count_window: 1,
+        }
+    }
+}
+
+
+impl Default for PacketPolicy
+{
+    fn default() -> Self
+    {
+        PacketPolicy::new()
+    }
+}
+
+
+// 설정 파일 내용(없는 항목은 기존 설정 유지)
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile
+{
+    baud_rate: Option<u32>,
+    firmware_dir: Option<PathBuf>,
+    public_key: Option<PathBuf>,
+    target: Option<String>,
+    retry: Option<RetryPolicy>,
+    packet: Option<PacketPolicy>,
+}
+
+
+#[derive(Clone)]
+pub struct UpdaterConfig
+{
+    pub discovery: Discovery,                   // 장치 검색 방법
+    pub baud_rate: u32,                         // 시리얼 포트 통신 속도
+    pub path_firmware: PathBuf,                 // 펌웨어 파일 폴더
+    pub vec_path_firmware_file: Vec<PathBuf>,   // 지정된 펌웨어 파일(있으면 폴더를 검색하지 않음)
+    pub flag_trace: bool,                       // 통신 기록을 파일로 저장
+    pub path_trace: PathBuf,                    // 통신 기록 파일 폴더
+    pub path_capture: Option<PathBuf>,          // 주고 받은 데이터를 재현할 수 있도록 저장할 폴더(없으면 저장하지 않음)
+    pub flag_force_reflash: bool,               // 장치와 파일의 버전이 같아도 업데이트
+    pub flag_allow_downgrade: bool,             // 장치보다 낮은 버전의 파일로 업데이트 허용
+    pub path_session: Option<PathBuf>,          // 중단된 업데이트를 이어서 하기 위한 기록 파일(없으면 기록하지 않음)
+    pub path_public_key: Option<PathBuf>,       // 펌웨어 서명 확인용 공개키 파일(있으면 서명이 있는 펌웨어만 사용)
+    pub flag_allow_unverified: bool,            // 확인할 수 없는 펌웨어 파일도 사용(변조된 파일은 허용하지 않음)
+    pub device_type_target: Option<DeviceType>, // 업데이트할 장치(없으면 응답한 장치 중에서 선택)
+    pub flag_select_target: bool,               // 여러 장치가 응답하면 사용자가 고를 때까지 기다림(아니면 처음 응답한 장치)
+    pub flag_chain: bool,                       // 장치 하나의 업데이트가 끝나면 같은 연결의 다음 장치를 이어서 업데이트
+    pub flag_reboot_bootloader: bool,           // 애플리케이션 모드의 장치를 부트로더로 다시 시작하여 업데이트
+    pub flag_loop: bool,                        // 업데이트가 끝나면 장치가 분리될 때까지 기다린 후 다음 장치 업데이트
+    pub retry: RetryPolicy,                     // 응답 대기 시간과 재시도 한도
+    pub packet: PacketPolicy,                   // 패킷당 블럭 수
+}
+
+
+impl UpdaterConfig
+{
+    pub fn new() -> UpdaterConfig
+    {
+        UpdaterConfig
+        {
+            discovery: Discovery::Hotplug,
+            baud_rate: 57_600,
+            path_firmware: UpdaterConfig::default_path_firmware(),
+            vec_path_firmware_file: Vec::new(),
+            flag_trace: false,
+            path_trace: UpdaterConfig::default_path_trace(),
+            path_capture: None,
+            flag_force_reflash: false,
+            flag_allow_downgrade: false,
+            path_session: None,
+            path_public_key: None,
+            flag_allow_unverified: false,
+            device_type_target: None,
+            flag_select_target: false,
+            flag_chain: false,
+            flag_reboot_bootloader: false,
+            flag_loop: false,
+            retry: RetryPolicy::new(),
+            packet: PacketPolicy::new(),
+        }
+    }
+
+
+    // toml 설정 파일의 값을 적용
+    pub fn apply_file(&mut self, path: &Path) -> Result<(), String>
+    {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let file: ConfigFile = toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        if let Some(baud_rate) = file.baud_rate
+        {
+            self.baud_rate = baud_rate;
+        }
+
+        if let Some(firmware_dir) = file.firmware_dir
+        {
+            self.path_firmware = firmware_dir;
+        }
+
+        if let Some(public_key) = file.public_key
+        {
+            self.path_public_key = Some(public_key);
+        }
+
+        if let Some(target) = file.target
+        {
+            self.device_type_target = Some(parse_device_type(&target).map_err(|e| format!("{}: {}", path.display(), e))?);
+        }
+
+        if let Some(retry) = file.retry
+        {
+            self.retry = retry;
+        }
+
+        if let Some(packet) = file.packet
+        {
+            self.packet = packet;
+        }
+
+        Ok(())
+    }
+
+
+    // 실행 파일이 있는 폴더의 firmware 폴더
+    pub fn default_path_firmware() -> PathBuf
+    {
+        UpdaterConfig::path_next_to_exe("firmware")
+    }
+
+
+    // 실행 파일이 있는 폴더의 log 폴더
+    pub fn default_path_trace() -> PathBuf
+    {
+        UpdaterConfig::path_next_to_exe("log")
+    }
+
+
+    // 실행 파일이 있는 폴더의 세션 기록 파일
+    pub fn default_path_session() -> PathBuf
+    {
+        UpdaterConfig::path_next_to_exe("session.toml")
+    }
+
+
+    fn path_next_to_exe(name: &str) -> PathBuf
+    {
+        let mut path = match std::env::current_exe()
+        {
+            Ok(path) => path,
+            Err(_) => PathBuf::new(),
+        };
+
+        path.pop();
+        path.push(name);
+
+        path
+    }
+}
+
+
+impl Default for UpdaterConfig
+{
+    fn default() -> Self
+    {
+        UpdaterConfig::new()
+    }
+}
+
+
+// 명령행과 설정 파일에서 사용하는 장치 이름
+const LIST_DEVICE_TYPE: [(&str, DeviceType); 8] = [
+    ("drone", DeviceType::Drone),
+    ("controller", DeviceType::Controller),
+    ("link-client", DeviceType::LinkClient),
+    ("link-server", DeviceType::LinkServer),
+    ("ble-client", DeviceType::BleClient),
+    ("ble-server", DeviceType::BleServer),
+    ("tester", DeviceType::Tester),
+    ("monitor", DeviceType::Monitor),
+];
+
+
+pub fn parse_device_type(text: &str) -> Result<DeviceType, String>
+{
+    LIST_DEVICE_TYPE
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(text.trim()))
+        .map(|(_, device_type)| *device_type)
+        .ok_or_else(|| format!("Unknown device '{}', use one of {}",
+            text,
+            LIST_DEVICE_TYPE.iter().map(|(name, _)| *name).collect::<Vec<&str>>().join(", ")))
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn file_overrides_only_given_values()
+    {
+        let path = std::env::temp_dir().join(format!("drone_updater_config_{}.toml", std::process::id()));
+        fs::write(&path, "baud_rate = 115200\ntarget = \"Controller\"\n\n[retry]\ntime_location_timeout_ms = 5000\ncount_error_max = 100\n").unwrap();
+
+        let mut config = UpdaterConfig::new();
+        let result = config.apply_file(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(config.baud_rate, 115200);
+        assert_eq!(config.device_type_target, Some(DeviceType::Controller));
+        assert_eq!(config.path_firmware, UpdaterConfig::default_path_firmware());
+        assert_eq!(config.retry.time_location_timeout_ms, 5000);
+        assert_eq!(config.retry.count_error_max, 100);
+        assert_eq!(config.retry.time_interval_ms, 200);
+    }
+
+    #[test]
+    fn misspelled_key_is_rejected()
+    {
+        let path = std::env::temp_dir().join(format!("drone_updater_config_typo_{}.toml", std::process::id()));
+        let mut config = UpdaterConfig::new();
+
+        fs::write(&path, "baud_rat = 115200\n").unwrap();
+        let error_top = config.apply_file(&path).unwrap_err();
+
+        fs::write(&path, "[retry]\ncount_error_mx = 100\n").unwrap();
+        let error_retry = config.apply_file(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert!(error_top.contains("unknown field `baud_rat`"), "{}", error_top);
+        assert!(error_retry.contains("unknown field `count_error_mx`"), "{}", error_retry);
+        assert_eq!(config.retry.count_error_max, 30);
+    }
+}
@@ -0,0 +1,453 @@
+use std::{
+    fs::File,
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use e_drone::file::{EncryptedBinary, EncryptedBinaryHeader};
+use e_drone::communication::{*};
+use e_drone::communication::receiver::{*};
+use e_drone::system::{*};
+use e_drone::protocol::{*};
+use e_drone::protocol::command::CommandType;
+
+use crate::bundle::{self, Manifest, ManifestEntry};
+use crate::config::UpdaterConfig;
+use crate::transport::{ChannelTransport, Transport};
+use crate::updater::{Sequence, Updater};
+
+
+// e_drone 부트로더를 흉내내는 가상 장치(실제 장치 없이 업데이트 과정을 시험하기 위해 사용)
+pub struct Emulator
+{
+    transport: Box<dyn Transport>,
+    buffer: [u8; 1024],
+    receiver: Receiver,
+
+    device_type: DeviceType,
+    information: Information,
+    update_location: UpdateLocation,
+    index_block_end: u16,       // 첫 블럭의 헤더를 받은 후 결정됨
+    vec_image: Vec<u8>,         // 수신한 펌웨어 이미지
+    flag_boot_old_image: bool,  // 업데이트 후 이전 펌웨어로 시작(검증 실패 시험용)
+    count_block_max: u16,       // 한 번에 받을 수 있는 최대 블럭 수(넘으면 응답하지 않음)
+    count_drop: u32,            // 업데이트 패킷을 이 수마다 하나씩 버림(0이면 버리지 않음, 통신 손실 시험용)
+    count_update: u32,          // 받은 업데이트 패킷 수
+    count_command_ignore: u32,  // 처음 받은 명령을 이 수만큼 무시(명령 손실 시험용)
+    bridge: Option<(ChannelTransport, Box<Emulator>)>,  // 이 장치를 거쳐 연결된 장치(예: 조종기에 연결된 드론)
+}
+
+
+impl Emulator
+{
+    pub fn new(transport: Box<dyn Transport>, device_type: DeviceType, model_number: ModelNumber, version: Version, mode_update: ModeUpdate) -> Emulator
+    {
+        let mut information = Information::new();
+        information.mode_update = mode_update;
+        information.model_number = model_number;
+        information.version = version;
+
+        Emulator
+        {
+            transport,
+            buffer: [0u8; 1024],
+            receiver: Receiver::new(),
+
+            device_type,
+            information,
+            update_location: UpdateLocation::new(),
+            index_block_end: 0,
+            vec_image: Vec::new(),
+            flag_boot_old_image: false,
+            count_block_max: u16::MAX,
+            count_drop: 0,
+            count_update: 0,
+            count_command_ignore: 0,
+            bridge: None,
+        }
+    }
+
+
+    pub fn get_information(&self) -> &Information
+    {
+        &self.information
+    }
+
+
+    pub fn get_update_location(&self) -> &UpdateLocation
+    {
+        &self.update_location
+    }
+
+
+    pub fn get_image(&self) -> &[u8]
+    {
+        &self.vec_image
+    }
+
+
+    // 업데이트가 index_block_next 위치에서 중단된 상태로 설정
+    pub fn set_partial(&mut self, eb: &EncryptedBinary, index_block_next: u16)
+    {
+        self.vec_image = eb.data_array[..(index_block_next as usize) << 4].to_vec();
+        self.update_location.index_block_next = index_block_next;
+        self.index_block_end = ((EncryptedBinaryHeader::size() + eb.header.length as usize + 15) >> 4) as u16;
+        self.information.mode_update = ModeUpdate::Update;
+    }
+
+
+    pub fn set_boot_old_image(&mut self, flag_boot_old_image: bool)
+    {
+        self.flag_boot_old_image = flag_boot_old_image;
+    }
+
+
+    pub fn set_count_block_max(&mut self, count_block_max: u16)
+    {
+        self.count_block_max = count_block_max;
+    }
+
+
+    pub fn set_count_drop(&mut self, count_drop: u32)
+    {
+        self.count_drop = count_drop;
+    }
+
+
+    pub fn set_count_command_ignore(&mut self, count_command_ignore: u32)
+    {
+        self.count_command_ignore = count_command_ignore;
+    }
+
+
+    // 이 장치를 거쳐 연결된 장치 추가(다른 장치로 보내는 데이터를 전달하고 응답을 돌려줌)
+    pub fn set_bridge(&mut self, device_type: DeviceType, model_number: ModelNumber, version: Version, mode_update: ModeUpdate)
+    {
+        let (transport_bridge, transport_device) = ChannelTransport::pair();
+        let emulator = Emulator::new(Box::new(transport_device), device_type, model_number, version, mode_update);
+
+        self.bridge = Some((transport_bridge, Box::new(emulator)));
+    }
+
+
+    pub fn get_bridge(&self) -> Option<&Emulator>
+    {
+        self.bridge.as_ref().map(|(_, emulator)| emulator.as_ref())
+    }
+
+
+    // 업데이트 완료 후 새 펌웨어로 애플리케이션 시작
+    fn reboot(&mut self)
+    {
+        self.information.mode_update = ModeUpdate::RunApplication;
+
+        if self.flag_boot_old_image
+        {
+            return;
+        }
+
+        if let Ok(header) = EncryptedBinaryHeader::parse(&self.vec_image[..EncryptedBinaryHeader::size()])
+        {
+            self.information.model_number = header.model_number;
+            self.information.version = header.version;
+        }
+    }
+
+
+    fn send(&mut self, data_type: DataType, vec_data: &Vec<u8>)
+    {
+        let _ = self.transport.write(&transfer::transfer(data_type, self.device_type, DeviceType::Base, vec_data));
+    }
+
+
+    // 수신한 데이터를 모두 처리
+    pub fn run(&mut self)
+    {
+        if let Ok(length_read) = self.transport.read(&mut self.buffer)
+        {
+            if length_read > 0
+            {
+                self.receiver.push_slice(&self.buffer[..length_read]);
+            }
+        }
+
+        while let messaging::State::Loaded = self.receiver.check()
+        {
+            self.receiver.clear();
+
+            let header = *self.receiver.get_header();
+            if header.to != self.device_type && header.to != DeviceType::Broadcasting
+            {
+                if let Some((transport_bridge, _)) = &mut self.bridge
+                {
+                    let _ = transport_bridge.write(&transfer::transfer_header_data(&header, self.receiver.get_data()));
+                }
+                continue;
+            }
+
+            match handler::check(&header, self.receiver.get_data())
+            {
+                Data::Request(request) => self.run_request(request.data_type),
+                Data::Update(update) => self.run_update(update),
+                Data::Command(command) => self.run_command(command.command_type),
+                _ => {}
+            }
+        }
+
+        // 연결된 장치의 응답을 그대로 전달
+        if let Some((transport_bridge, emulator)) = &mut self.bridge
+        {
+            emulator.run();
+
+            while let Ok(length_read) = transport_bridge.read(&mut self.buffer)
+            {
+                if length_read == 0
+                {
+                    break;
+                }
+
+                let _ = self.transport.write(&self.buffer[..length_read]);
+            }
+        }
+    }
+
+
+    fn run_request(&mut self, data_type: DataType)
+    {
+        match data_type
+        {
+            DataType::Information =>
+            {
+                if self.information.mode_update == ModeUpdate::Complete
+                {
+                    self.reboot();
+                }
+
+                self.send(DataType::Information, &self.information.to_vec());
+            }
+            DataType::UpdateLocation => self.send(DataType::UpdateLocation, &self.update_location.to_vec()),
+            _ => {}
+        }
+    }
+
+
+    // 애플리케이션 모드에서 부트로더로 이동 명령을 받으면 부트로더로 다시 시작
+    fn run_command(&mut self, command_type: CommandType)
+    {
+        if self.count_command_ignore > 0
+        {
+            self.count_command_ignore -= 1;
+            return;
+        }
+
+        if command_type == CommandType::JumpToBootloader && self.information.mode_update == ModeUpdate::RunApplication
+        {
+            self.information.mode_update = ModeUpdate::Ready;
+        }
+    }
+
+
+    fn run_update(&mut self, update: Update)
+    {
+        if self.information.mode_update != ModeUpdate::Ready && self.information.mode_update != ModeUpdate::Update
+        {
+            return;
+        }
+
+        // 수신 버퍼보다 큰 패킷은 버림
+        if update.vec_data.len() > (self.count_block_max as usize) << 4
+        {
+            return;
+        }
+
+        self.count_update += 1;
+        if self.count_drop > 0 && self.count_update.is_multiple_of(self.count_drop)
+        {
+            return;
+        }
+
+        // 기다리는 위치의 데이터인 경우에만 저장하고, 아닌 경우 현재 위치를 다시 알려줌
+        if update.index_block_next == self.update_location.index_block_next && !update.vec_data.is_empty()
+        {
+            self.information.mode_update = ModeUpdate::Update;
+
+            self.vec_image.truncate((update.index_block_next as usize) << 4);
+            self.vec_image.extend_from_slice(&update.vec_data);
+            self.update_location.index_block_next += ((update.vec_data.len() + 15) >> 4) as u16;
+
+            // 첫 블럭은 펌웨어 헤더이며 헤더에 기록된 길이로 전체 블럭 수를 계산
+            if self.index_block_end == 0 && self.vec_image.len() >= EncryptedBinaryHeader::size()
+            {
+                if let Ok(header) = EncryptedBinaryHeader::parse(&self.vec_image[..EncryptedBinaryHeader::size()])
+                {
+                    self.index_block_end = ((EncryptedBinaryHeader::size() + header.length as usize + 15) >> 4) as u16;
+                }
+            }
+        }
+
+        self.send(DataType::UpdateLocation, &self.update_location.to_vec());
+
+        if self.index_block_end > 0 && self.update_location.index_block_next >= self.index_block_end
+        {
+            self.information.mode_update = ModeUpdate::Complete;
+            self.send(DataType::Information, &self.information.to_vec());
+        }
+    }
+}
+
+
+// 시험용 펌웨어 파일 생성(헤더 + length_body 크기의 데이터)
+pub fn build_firmware(model_number: ModelNumber, version: Version, length_body: usize) -> EncryptedBinary
+{
+    let mut eb = EncryptedBinary::new();
+
+    eb.header.model_number = model_number;
+    eb.header.version = version;
+    eb.header.length = length_body as u32;
+
+    eb.data_array.extend_from_slice(&model_number.to_array());
+    eb.data_array.extend_from_slice(&version.to_array());
+    eb.data_array.extend_from_slice(&eb.header.length.to_le_bytes());
+    eb.data_array.extend_from_slice(&eb.header.year.to_le_bytes());
+    eb.data_array.push(eb.header.month);
+    eb.data_array.push(eb.header.day);
+
+    for i in 0..length_body
+    {
+        eb.data_array.push((i * 7 + (i >> 8)) as u8);
+    }
+
+    eb.flag_open = true;
+    eb
+}
+
+
+// 시험용 묶음 파일 생성(manifest.toml + 목록의 파일)
+pub fn write_bundle(path: &Path, vec_file: &[(&ManifestEntry, &[u8])])
+{
+    let manifest = Manifest{ firmware: vec_file.iter().map(|(entry, _)| (*entry).clone()).collect() };
+    let text = toml::to_string(&manifest).unwrap();
+
+    let mut builder = tar::Builder::new(File::create(path).unwrap());
+    for (name, data_array) in [(bundle::NAME_MANIFEST, text.as_bytes())].into_iter().chain(vec_file.iter().map(|(entry, data_array)| (entry.file.as_str(), *data_array)))
+    {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data_array.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, data_array).unwrap();
+    }
+    builder.finish().unwrap();
+}
+
+
+// 시험용 설정(시험용 펌웨어 파일에는 SHA-256, 서명 확인용 파일이 없으므로 확인 없이 사용)
+pub fn config_unverified() -> UpdaterConfig
+{
+    let mut config = UpdaterConfig::new();
+    config.flag_allow_unverified = true;
+    config
+}
+
+
+// Updater를 새 가상 장치에 연결하고 가상 장치를 반환
+pub fn attach(updater: &mut Updater, device_type: DeviceType, model_number: ModelNumber, version: Version, mode_update: ModeUpdate) -> Emulator
+{
+    let (transport_updater, transport_device) = ChannelTransport::pair();
+    updater.connect(Box::new(transport_updater));
+
+    Emulator::new(Box::new(transport_device), device_type, model_number, version, mode_update)
+}
+
+
+// 펌웨어 파일 목록으로 Updater를 만들고 새 가상 장치에 연결
+pub fn connect(config: UpdaterConfig, vec_file: impl Into<Arc<Vec<EncryptedBinary>>>, device_type: DeviceType, model_number: ModelNumber, version: Version, mode_update: ModeUpdate) -> (Updater, Emulator)
+{
+    let mut updater = Updater::with_firmware(config, vec_file);
+    let emulator = attach(&mut updater, device_type, model_number, version, mode_update);
+
+    (updater, emulator)
+}
+
+
+// 업데이트가 끝나거나 제한 시간을 넘을 때까지 Updater와 Emulator를 번갈아 실행
+pub fn run_until_finished(updater: &mut Updater, emulator: &mut Emulator, time_limit: Duration) -> Sequence
+{
+    let time_start = Instant::now();
+
+    while !updater.get_sequence().is_finished() && time_start.elapsed() < time_limit
+    {
+        updater.run();
+        emulator.run();
+
+        thread::sleep(Duration::from_micros(200));
+    }
+
+    *updater.get_sequence()
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn version(major: u8, minor: u8, build: u16) -> Version
+    {
+        Version{ major, minor, build }
+    }
+
+    #[test]
+    fn full_update_transfers_image()
+    {
+        let eb = build_firmware(ModelNumber::Drone3DroneP5, version(22, 2, 3), 16 * 40);
+        let vec_expected = eb.data_array.clone();
+
+        let (mut updater, mut emulator) = connect(config_unverified(), vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP5, version(22, 1, 1), ModeUpdate::Ready);
+
+        assert_eq!(run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10)), Sequence::UpdateComplete);
+        assert_eq!(emulator.get_information().mode_update, ModeUpdate::RunApplication);
+        assert_eq!(emulator.get_information().version.to_u32(), version(22, 2, 3).to_u32());
+        assert_eq!(emulator.get_image(), &vec_expected[..]);
+    }
+
+    #[test]
+    fn unknown_model_is_not_updated()
+    {
+        let eb = build_firmware(ModelNumber::Drone3DroneP5, version(22, 2, 3), 16 * 4);
+
+        let (mut updater, mut emulator) = connect(config_unverified(), vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP6, version(22, 1, 1), ModeUpdate::Ready);
+
+        assert_eq!(run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10)), Sequence::NoMatchedFirmwareFile);
+        assert!(emulator.get_image().is_empty());
+    }
+
+    #[test]
+    fn application_mode_is_reported()
+    {
+        let eb = build_firmware(ModelNumber::Drone3DroneP5, version(22, 2, 3), 16 * 4);
+
+        let (mut updater, mut emulator) = connect(config_unverified(), vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP5, version(22, 1, 1), ModeUpdate::RunApplication);
+
+        assert_eq!(run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10)), Sequence::ApplicationMode);
+    }
+
+    #[test]
+    fn application_mode_reboots_into_bootloader()
+    {
+        let eb = build_firmware(ModelNumber::Drone3DroneP5, version(22, 2, 3), 16 * 4);
+        let vec_expected = eb.data_array.clone();
+
+        let mut config = config_unverified();
+        config.flag_reboot_bootloader = true;
+
+        let (mut updater, mut emulator) = connect(config, vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP5, version(22, 1, 1), ModeUpdate::RunApplication);
+        emulator.set_count_command_ignore(2);
+
+        assert_eq!(run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10)), Sequence::UpdateComplete);
+        assert_eq!(emulator.get_image(), &vec_expected[..]);
+    }
+}
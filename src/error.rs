@@ -0,0 +1,192 @@
+use std::{
+    error::Error,
+    fmt,
+    io,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use e_drone::system::{DeviceType, ModelNumber, Version};
+
+use crate::firmware::format_version;
+use crate::verify::Verification;
+
+
+// 오류가 발생했을 때의 업데이트 진행 상황
+#[derive(Debug, Clone)]
+pub struct ErrorContext
+{
+    pub port: Option<String>,               // 연결된 포트
+    pub device_type: DeviceType,            // 업데이트 대상 장치
+    pub model_number: ModelNumber,          // 장치 모델 번호
+    pub index_block: u16,                   // 장치가 받은 마지막 블럭 위치
+    pub count_retry: u32,                   // 연결 후 전체 재전송 횟수
+    pub time_elapsed: Duration,             // 장치 연결 후 경과 시간
+    pub error_io: Option<Arc<io::Error>>,   // 마지막으로 발생한 통신 오류
+}
+
+
+impl fmt::Display for ErrorContext
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "port: {}, device: {:?}, model: {:?}, block: {}, retry: {}, elapsed: {:.1}s",
+            self.port.as_deref().unwrap_or("-"),
+            self.device_type,
+            self.model_number,
+            self.index_block,
+            self.count_retry,
+            self.time_elapsed.as_secs_f32())?;
+
+        if let Some(error_io) = &self.error_io
+        {
+            write!(f, ", io: {}", error_io)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub enum UpdateError
+{
+    NoFirmwareFile { path: PathBuf },       // 펌웨어 파일이 없음
+    NoAnswer(ErrorContext),                 // 장치로부터 응답이 없음
+    NoMatchedFirmwareFile(ErrorContext),    // 장치와 일치하는 펌웨어 파일이 없음
+    ApplicationMode(ErrorContext),          // 장치가 애플리케이션 모드로 동작 중
+    UpdateTimeOver(ErrorContext),           // 업데이트 제한 시간 초과
+    DowngradeRefused { context: ErrorContext, version_device: Version, version_firmware: Version },    // 낮은 버전으로 업데이트 거부
+    ResumeRefused { context: ErrorContext, path_session: PathBuf },     // 중단된 업데이트와 펌웨어 파일이 다름
+    ResumeMismatch { context: ErrorContext, path_session: PathBuf },    // 기록된 위치가 장치가 알려온 위치보다 앞섬
+    VerifyFailed { context: ErrorContext, model_number_firmware: ModelNumber, version_firmware: Version, version_device: Option<Version> },    // 업데이트 후 설치된 펌웨어가 다름(응답이 없으면 version_device는 None)
+    FirmwareRejected { context: ErrorContext, path: PathBuf, verification: Verification },    // 확인되지 않았거나 변조된 펌웨어 파일
+}
+
+
+impl UpdateError
+{
+    pub fn get_context(&self) -> Option<&ErrorContext>
+    {
+        match self
+        {
+            UpdateError::NoFirmwareFile{ .. } => None,
+            UpdateError::NoAnswer(context) |
+            UpdateError::NoMatchedFirmwareFile(context) |
+            UpdateError::ApplicationMode(context) |
+            UpdateError::UpdateTimeOver(context) |
+            UpdateError::DowngradeRefused{ context, .. } |
+            UpdateError::ResumeRefused{ context, .. } |
+            UpdateError::ResumeMismatch{ context, .. } |
+            UpdateError::VerifyFailed{ context, .. } |
+            UpdateError::FirmwareRejected{ context, .. } => Some(context),
+        }
+    }
+}
+
+
+impl fmt::Display for UpdateError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            UpdateError::NoFirmwareFile{ path } => write!(f, "No firmware file in {}", path.display()),
+            UpdateError::NoAnswer(context) => write!(f, "No answer from device ({})", context),
+            UpdateError::NoMatchedFirmwareFile(context) => write!(f, "Can't find matched firmware file ({})", context),
+            UpdateError::ApplicationMode(context) => write!(f, "Device is running application, reconnect with bootloader mode ({})", context),
+            UpdateError::UpdateTimeOver(context) => write!(f, "Update time over ({})", context),
+            UpdateError::DowngradeRefused{ context, version_device, version_firmware } => write!(f, "Downgrade from {} to {} refused ({})",
+                format_version(version_device),
+                format_version(version_firmware),
+                context),
+            UpdateError::ResumeRefused{ context, path_session } => write!(f, "Interrupted update was started with a different firmware file, see {} ({})",
+                path_session.display(),
+                context),
+            UpdateError::ResumeMismatch{ context, path_session } => write!(f, "Device is behind the interrupted update, see {} ({})",
+                path_session.display(),
+                context),
+            UpdateError::VerifyFailed{ context, model_number_firmware, version_firmware, version_device: Some(version_device) } => write!(f, "Device runs {:?} {} after update, expected {:?} {} ({})",
+                context.model_number,
+                format_version(version_device),
+                model_number_firmware,
+                format_version(version_firmware),
+                context),
+            UpdateError::VerifyFailed{ context, model_number_firmware, version_firmware, version_device: None } => write!(f, "Device didn't come back after update to {:?} {} ({})",
+                model_number_firmware,
+                format_version(version_firmware),
+                context),
+            UpdateError::FirmwareRejected{ context, path, verification } => write!(f, "Firmware file {} rejected: {} ({})",
+                path.display(),
+                verification,
+                context),
+        }
+    }
+}
+
+
+impl Error for UpdateError
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)>
+    {
+        match self.get_context()
+        {
+            Some(ErrorContext{ error_io: Some(error_io), .. }) => Some(error_io.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use e_drone::system::ModeUpdate;
+
+    use crate::emulator;
+    use crate::updater::Sequence;
+
+    #[test]
+    fn unmatched_model_reports_context()
+    {
+        let version = Version{ major: 22, minor: 1, build: 1 };
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, version, 16 * 8);
+
+        let (mut updater, mut emulator) = emulator::connect(emulator::config_unverified(), vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP6, version, ModeUpdate::Ready);
+
+        assert_eq!(emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10)), Sequence::NoMatchedFirmwareFile);
+
+        match updater.get_error()
+        {
+            Some(UpdateError::NoMatchedFirmwareFile(context)) =>
+            {
+                assert_eq!(context.port.as_deref(), Some("channel-a"));
+                assert_eq!(context.device_type, DeviceType::Drone);
+                assert_eq!(context.model_number, ModelNumber::Drone3DroneP6);
+            }
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn io_error_is_exposed_as_source()
+    {
+        let error = UpdateError::NoAnswer(ErrorContext
+        {
+            port: Some(String::from("COM3")),
+            device_type: DeviceType::Drone,
+            model_number: ModelNumber::Drone3DroneP5,
+            index_block: 120,
+            count_retry: 31,
+            time_elapsed: Duration::from_secs(12),
+            error_io: Some(Arc::new(io::Error::new(io::ErrorKind::BrokenPipe, "unplugged"))),
+        });
+
+        assert!(error.to_string().contains("block: 120"));
+        assert_eq!(error.source().unwrap().to_string(), "unplugged");
+    }
+}
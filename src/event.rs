@@ -0,0 +1,63 @@
+use e_drone::protocol::Information;
+use e_drone::system::{DeviceType, ModelNumber, Version};
+
+use crate::error::UpdateError;
+use crate::updater::{DeviceResult, Sequence};
+use crate::verify::Verification;
+
+
+// Updater에서 발생하는 사건(subscribe로 받은 채널을 통해 전달)
+#[derive(Debug, Clone)]
+pub enum Event
+{
+    DeviceConnected { port: String },                                   // 장치 연결
+    InformationReceived { device_type: DeviceType, information: Information },  // 장치 정보 수신
+    FirmwareMatched { model_number: ModelNumber, version_device: Version, version_firmware: Version },  // 장치와 일치하는 펌웨어 파일 확인
+    FirmwareVerified { verification: Verification, accepted: bool },  // 펌웨어 파일의 SHA-256, 서명 확인 결과
+    LocationReceived { index_block_next: u16 },                         // 업데이트 위치 수신
+    Resumed { index_block_next: u16, index_block_end: u16 },            // 중단된 업데이트를 이어서 진행
+    BlockAcknowledged { index_block_next: u16, index_block_end: u16 },  // 장치가 데이터 블럭을 받았음
+    Retry { index_block_next: u16, count_error: u32 },                  // 응답이 없어 같은 블럭을 다시 전송
+    PacketSizeChanged { count_block: u16 },                             // 패킷당 블럭 수 변경
+    StateChanged { sequence: Sequence },                                // 상태 변경
+    DeviceFinished { result: DeviceResult },                            // 연속 업데이트에서 장치 하나가 끝남
+    Completed,                                                          // 업데이트 완료
+    Failed { error: UpdateError },                                      // 오류로 종료
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use std::time::Duration;
+
+    use e_drone::system::ModeUpdate;
+
+    use crate::emulator;
+    use crate::updater::Updater;
+
+    #[test]
+    fn full_update_emits_events_in_order()
+    {
+        let version = Version{ major: 22, minor: 1, build: 1 };
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, version, 16 * 8);
+
+        let mut updater = Updater::with_firmware(emulator::config_unverified(), vec![eb]);
+        let rx_event = updater.subscribe();
+        let mut emulator = emulator::attach(&mut updater, DeviceType::Drone, ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 0, build: 9 }, ModeUpdate::Ready);
+
+        emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10));
+
+        let vec_event: Vec<Event> = rx_event.try_iter().collect();
+
+        assert!(matches!(vec_event[0], Event::DeviceConnected{ .. }));
+        assert!(matches!(vec_event[1], Event::StateChanged{ sequence: Sequence::CheckDeviceInformation }));
+        assert!(vec_event.iter().any(|event| matches!(event, Event::InformationReceived{ device_type: DeviceType::Drone, .. })));
+        assert!(vec_event.iter().any(|event| matches!(event, Event::FirmwareMatched{ model_number: ModelNumber::Drone3DroneP5, .. })));
+        assert!(vec_event.iter().any(|event| matches!(event, Event::BlockAcknowledged{ index_block_next: 9, index_block_end: 9 })));
+        assert!(matches!(vec_event.last(), Some(Event::Completed)));
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use e_drone::file::{EncryptedBinary, EncryptedBinaryHeader};
+use e_drone::system::{ModelNumber, Version};
+
+use crate::bundle::{self, ManifestEntry};
+use crate::config::UpdaterConfig;
+use crate::verify::{EXTENSION_CHECKSUM, EXTENSION_SIGNATURE};
+
+
+// 펌웨어 파일 목록 표시용 정보
+#[derive(Debug, Clone)]
+pub struct FirmwareInformation
+{
+    pub model_number: ModelNumber,
+    pub version: Version,
+    pub size: usize,            // 파일 크기(byte)
+    pub count_block: usize,     // 전송할 블럭 수(16 byte 단위)
+    pub manifest: Option<ManifestEntry>,    // 묶음 파일 안의 펌웨어인 경우 목록의 정보
+}
+
+
+#[derive(Debug, Clone)]
+pub struct FirmwareEntry
+{
+    pub path: PathBuf,
+    pub result: Result<FirmwareInformation, String>,    // 읽지 못한 경우 그 이유
+}
+
+
+impl FirmwareInformation
+{
+    fn new(eb: &EncryptedBinary, manifest: Option<ManifestEntry>) -> FirmwareInformation
+    {
+        FirmwareInformation
+        {
+            model_number: eb.header.model_number,
+            version: eb.header.version,
+            size: eb.data_array.len(),
+            count_block: eb.data_array.len() >> 4,
+            manifest,
+        }
+    }
+}
+
+
+impl FirmwareEntry
+{
+    // 묶음 파일은 안에 있는 펌웨어마다 하나씩(읽지 못하면 묶음 파일 하나)
+    pub fn read(path: &Path) -> Vec<FirmwareEntry>
+    {
+        if bundle::is_bundle(path)
+        {
+            return match bundle::read_bundle(path)
+            {
+                Ok(vec_image) => vec_image
+                    .into_iter()
+                    .map(|image| FirmwareEntry
+                    {
+                        path: path.join(&image.entry.file),
+                        result: Ok(FirmwareInformation::new(&image.eb, Some(image.entry))),
+                    })
+                    .collect(),
+
+                Err(e) => vec![FirmwareEntry{ path: path.to_path_buf(), result: Err(e) }],
+            };
+        }
+
+        vec![FirmwareEntry
+        {
+            path: path.to_path_buf(),
+            result: read_firmware_file(path).map(|eb| FirmwareInformation::new(&eb, None)),
+        }]
+    }
+}
+
+
+// EncryptedBinary::read와 같은 방식으로 읽되 실패한 이유를 함께 반환
+pub fn read_firmware_file(path: &Path) -> Result<EncryptedBinary, String>
+{
+    let data_array = fs::read(path).map_err(|e| e.to_string())?;
+
+    parse_firmware_file(&path.to_string_lossy(), data_array)
+}
+
+
+// 이미 읽은 파일 내용으로 EncryptedBinary 생성
+pub fn parse_firmware_file(file_name: &str, data_array: Vec<u8>) -> Result<EncryptedBinary, String>
+{
+    if data_array.len() <= EncryptedBinaryHeader::size()
+    {
+        return Err(format!("File is too short ({} bytes)", data_array.len()));
+    }
+
+    let header = EncryptedBinaryHeader::parse(&data_array[..EncryptedBinaryHeader::size()])?;
+
+    let mut eb = EncryptedBinary::new();
+    eb.file_name = file_name.to_string();
+    eb.header = header;
+    eb.data_array = data_array;
+    eb.flag_open = true;
+
+    Ok(eb)
+}
+
+
+// 펌웨어 파일 하나 또는 묶음 파일 안의 펌웨어를 모두 읽음
+pub fn read_firmware_path(path: &Path) -> Result<Vec<EncryptedBinary>, String>
+{
+    match bundle::is_bundle(path)
+    {
+        true => Ok(bundle::read_bundle(path)?.into_iter().map(|image| image.eb).collect()),
+        false => Ok(vec![read_firmware_file(path)?]),
+    }
+}
+
+
+// 폴더 안의 파일 경로 목록(이름 순, 확인용 파일 제외)
+fn list_files(path: &Path) -> io::Result<Vec<PathBuf>>
+{
+    let mut vec_path: Vec<PathBuf> = fs::read_dir(path)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| !matches!(path.extension().and_then(|extension| extension.to_str()), Some(EXTENSION_CHECKSUM) | Some(EXTENSION_SIGNATURE)))
+        .collect();
+
+    vec_path.sort();
+
+    Ok(vec_path)
+}
+
+
+// 폴더 안에서 읽을 수 있는 펌웨어 파일을 모두 읽음
+pub fn lookup_firmware_files(path: &Path) -> Vec<EncryptedBinary>
+{
+    match list_files(path)
+    {
+        Ok(vec_path) => vec_path.iter().filter_map(|path| read_firmware_path(path).ok()).flatten().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+
+// 설정에 따라 지정된 파일 또는 폴더(묶음 파일) 안의 펌웨어 파일을 읽음
+// 지정된 파일과 묶음 파일은 하나라도 읽을 수 없으면 오류(폴더 안의 파일은 읽을 수 있는 것만 사용)
+pub fn load_firmware_files(config: &UpdaterConfig) -> Result<Vec<EncryptedBinary>, String>
+{
+    if config.vec_path_firmware_file.is_empty()
+    {
+        if config.path_firmware.is_file()
+        {
+            return read_firmware_path(&config.path_firmware)
+                .map_err(|e| format!("Can't read firmware bundle {}: {}", config.path_firmware.display(), e));
+        }
+
+        return Ok(lookup_firmware_files(&config.path_firmware));
+    }
+
+    let mut vec_file = Vec::new();
+
+    for path in config.vec_path_firmware_file.iter()
+    {
+        vec_file.extend(read_firmware_path(path).map_err(|e| format!("Can't read firmware file {}: {}", path.display(), e))?);
+    }
+
+    Ok(vec_file)
+}
+
+
+// 폴더 안의 모든 파일을 읽은 결과
+pub fn list_firmware_files(path: &Path) -> io::Result<Vec<FirmwareEntry>>
+{
+    Ok(list_files(path)?.iter().flat_map(|path| FirmwareEntry::read(path)).collect())
+}
+
+
+// 설정에 따라 사용할 펌웨어 파일을 읽은 결과
+pub fn list_firmware_entries(config: &UpdaterConfig) -> io::Result<Vec<FirmwareEntry>>
+{
+    match (config.vec_path_firmware_file.is_empty(), config.path_firmware.is_file())
+    {
+        (true, true) => Ok(FirmwareEntry::read(&config.path_firmware)),
+        (true, false) => list_firmware_files(&config.path_firmware),
+        (false, _) => Ok(config.vec_path_firmware_file.iter().flat_map(|path| FirmwareEntry::read(path)).collect()),
+    }
+}
+
+
+// 펌웨어 파일 전체의 SHA-256 값(16진수 문자열)
+pub fn hash_firmware(eb: &EncryptedBinary) -> String
+{
+    Sha256::digest(&eb.data_array)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+
+pub fn format_version(version: &Version) -> String
+{
+    format!("{}.{}.{}", version.major, version.minor, version.build)
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use crate::emulator;
+
+    #[test]
+    fn list_reports_valid_and_broken_files()
+    {
+        let path = std::env::temp_dir().join(format!("drone_updater_list_{}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 3, build: 7 }, 16 * 10);
+        fs::write(path.join("a_drone.eb"), &eb.data_array).unwrap();
+        fs::write(path.join("b_broken.eb"), [1u8, 2, 3]).unwrap();
+
+        let vec_entry = list_firmware_files(&path).unwrap();
+        fs::remove_dir_all(&path).unwrap();
+
+        assert_eq!(vec_entry.len(), 2);
+
+        let information = vec_entry[0].result.as_ref().unwrap();
+        assert_eq!(information.model_number, ModelNumber::Drone3DroneP5);
+        assert_eq!(format_version(&information.version), "22.3.7");
+        assert_eq!(information.size, 16 * 11);
+        assert_eq!(information.count_block, 11);
+
+        assert_eq!(vec_entry[1].result.as_ref().unwrap_err(), "File is too short (3 bytes)");
+    }
+
+    #[test]
+    fn unreadable_explicit_file_fails_early()
+    {
+        let path = std::env::temp_dir().join(format!("drone_updater_explicit_{}.eb", std::process::id()));
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 3, build: 7 }, 16 * 10);
+        fs::write(&path, &eb.data_array).unwrap();
+
+        let mut config = UpdaterConfig::new();
+        config.path_firmware = PathBuf::from("/nonexistent");
+        config.vec_path_firmware_file = vec![path.clone()];
+        let vec_file = load_firmware_files(&config).unwrap();
+
+        config.vec_path_firmware_file.push(PathBuf::from("/nonexistent/missing.eb"));
+        let error = load_firmware_files(&config).map(|_| ()).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(vec_file.len(), 1);
+        assert_eq!(vec_file[0].header.model_number, ModelNumber::Drone3DroneP5);
+        assert!(error.starts_with("Can't read firmware file /nonexistent/missing.eb"), "{}", error);
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    io::{self, Write},
+    sync::mpsc::Receiver,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::event::Event;
+use crate::firmware::format_version;
+use crate::updater::{Sequence, Updater};
+
+
+// 화면 없이 Updater를 실행하고 상태 변화를 한 줄에 하나씩 JSON으로 출력
+pub struct Headless<W: Write>
+{
+    writer: W,
+    time_start: Instant,
+    rx_event: Receiver<Event>,
+    flag_started: bool,
+}
+
+
+impl<W: Write> Headless<W>
+{
+    pub fn new(writer: W, updater: &mut Updater) -> Headless<W>
+    {
+        Headless
+        {
+            writer,
+            time_start: Instant::now(),
+            rx_event: updater.subscribe(),
+            flag_started: false,
+        }
+    }
+
+
+    // 업데이트가 끝날 때까지 실행하고 마지막 상태를 반환(반복 모드에서는 장치마다 결과를 출력하고 계속 실행)
+    pub fn run(&mut self, updater: &mut Updater) -> io::Result<Sequence>
+    {
+        loop
+        {
+            updater.run();
+
+            self.check(updater)?;
+
+            if updater.get_sequence().is_finished()
+            {
+                self.write_result(updater)?;
+
+                if !updater.is_loop_enabled()
+                {
+                    return Ok(*updater.get_sequence());
+                }
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+
+    // 쌓여 있는 이벤트를 출력
+    pub fn check(&mut self, updater: &Updater) -> io::Result<()>
+    {
+        // 처음 한 번은 이벤트와 관계 없이 현재 상태를 출력
+        if !self.flag_started
+        {
+            self.flag_started = true;
+            self.write_state(updater, *updater.get_sequence())?;
+        }
+
+        while let Ok(event) = self.rx_event.try_recv()
+        {
+            match event
+            {
+                Event::StateChanged{ sequence } => self.write_state(updater, sequence)?,
+
+                Event::DeviceConnected{ port } => self.write(json!({
+                    "type": "connected",
+                    "port": port,
+                }))?,
+
+                Event::InformationReceived{ device_type, information } => self.write(json!({
+                    "type": "information",
+                    "device_type": format!("{:?}", device_type),
+                    "model_number": format!("{:?}", information.model_number),
+                    "mode_update": format!("{:?}", information.mode_update),
+                    "version": format_version(&information.version),
+                }))?,
+
+                Event::FirmwareMatched{ model_number, version_device, version_firmware } => self.write(json!({
+                    "type": "firmware",
+                    "model_number": format!("{:?}", model_number),
+                    "version_device": format_version(&version_device),
+                    "version_firmware": format_version(&version_firmware),
+                }))?,
+
+                Event::FirmwareVerified{ verification, accepted } => self.write(json!({
+                    "type": "verification",
+                    "result": verification.to_string(),
+                    "accepted": accepted,
+                }))?,
+
+                Event::BlockAcknowledged{ index_block_next, index_block_end } =>
+                {
+                    let stats = updater.get_transfer_stats();
+
+                    self.write(json!({
+                        "type": "progress",
+                        "index_block": index_block_next,
+                        "index_block_end": index_block_end,
+                        "percent": stats.get_percent(),
+                        "eta_ms": stats.get_eta().map(|eta| eta.as_millis() as u64),
+                        "bytes_per_sec": stats.throughput,
+                        "retransmissions": stats.count_retransmission,
+                        "blocks_per_packet": stats.count_block_per_packet,
+                        "window": stats.count_window,
+                    }))?;
+                }
+
+                Event::Resumed{ index_block_next, index_block_end } => self.write(json!({
+                    "type": "resumed",
+                    "index_block": index_block_next,
+                    "index_block_end": index_block_end,
+                }))?,
+
+                Event::PacketSizeChanged{ count_block } => self.write(json!({
+                    "type": "packet_size",
+                    "blocks_per_packet": count_block,
+                }))?,
+
+                Event::DeviceFinished{ result } => self.write(json!({
+                    "type": "device_result",
+                    "device_type": format!("{:?}", result.device_type),
+                    "model_number": format!("{:?}", result.model_number),
+                    "version": format_version(&result.version),
+                    "sequence": format!("{:?}", result.sequence),
+                    "success": !result.sequence.is_error(),
+                }))?,
+
+                Event::Retry{ index_block_next, count_error } => self.write(json!({
+                    "type": "retry",
+                    "index_block": index_block_next,
+                    "count_error": count_error,
+                }))?,
+
+                // 위치 수신과 종료 결과는 상태 변경과 result 줄로 충분함
+                Event::LocationReceived{ .. } | Event::Completed | Event::Failed{ .. } => {}
+            }
+        }
+
+        Ok(())
+    }
+
+
+    fn write_state(&mut self, updater: &Updater, sequence: Sequence) -> io::Result<()>
+    {
+        self.write(json!({
+            "type": "state",
+            "sequence": format!("{:?}", sequence),
+            "message": updater.get_message_status(),
+            "version": updater.get_message_version(),
+            "port": updater.get_port_description(),
+        }))
+    }
+
+
+    fn write_result(&mut self, updater: &Updater) -> io::Result<()>
+    {
+        let sequence = updater.get_sequence();
+        let stats = updater.get_transfer_stats();
+
+        let mut value = json!({
+            "type": "result",
+            "sequence": format!("{:?}", sequence),
+            "success": !sequence.is_error(),
+            "message": updater.get_message_status(),
+            "error": updater.get_error().map(|error| error.to_string()),
+            "version": updater.get_message_version(),
+            "bytes_sent": stats.count_byte_sent,
+            "retransmissions": stats.count_retransmission,
+            "transfer_ms": stats.get_time_phase(Sequence::FirmwareUpdate).as_millis() as u64,
+            "devices": updater.get_device_results()
+                .iter()
+                .map(|result| json!({
+                    "device_type": format!("{:?}", result.device_type),
+                    "model_number": format!("{:?}", result.model_number),
+                    "sequence": format!("{:?}", result.sequence),
+                }))
+                .collect::<Vec<Value>>(),
+        });
+
+        // 반복 모드에서는 지금까지 성공, 실패한 장치 수를 함께 출력
+        if updater.is_loop_enabled()
+        {
+            let (count_pass, count_fail) = updater.get_counts();
+            value["passed"] = json!(count_pass);
+            value["failed"] = json!(count_fail);
+        }
+
+        self.write(value)
+    }
+
+
+    fn write(&mut self, mut value: Value) -> io::Result<()>
+    {
+        value["time_ms"] = json!(self.time_start.elapsed().as_millis() as u64);
+
+        writeln!(self.writer, "{}", value)?;
+        self.writer.flush()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use e_drone::system::{DeviceType, ModeUpdate, ModelNumber, Version};
+
+    use crate::emulator;
+
+    #[test]
+    fn emits_state_progress_and_result_lines()
+    {
+        let version = Version{ major: 22, minor: 1, build: 1 };
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, version, 16 * 20);
+
+        let (mut updater, mut emulator) = emulator::connect(emulator::config_unverified(), vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 0, build: 9 }, ModeUpdate::Ready);
+
+        let mut headless = Headless::new(Vec::new(), &mut updater);
+        let time_start = Instant::now();
+        while !updater.get_sequence().is_finished() && time_start.elapsed() < Duration::from_secs(10)
+        {
+            updater.run();
+            emulator.run();
+            headless.check(&updater).unwrap();
+        }
+        headless.write_result(&updater).unwrap();
+
+        let output = String::from_utf8(headless.writer).unwrap();
+        let vec_line: Vec<Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+
+        assert_eq!(vec_line[0]["sequence"], "CheckDeviceInformation");
+        assert!(vec_line.iter().any(|line| line["type"] == "information" && line["model_number"] == "Drone3DroneP5"));
+        assert!(vec_line.iter().any(|line| line["type"] == "progress" && line["index_block"] == 20 + 1));
+
+        let result = vec_line.last().unwrap();
+        assert_eq!(result["type"], "result");
+        assert_eq!(result["sequence"], "UpdateComplete");
+        assert_eq!(result["success"], true);
+    }
+}
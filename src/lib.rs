@@ -1,5 +1,20 @@
+pub mod bundle;
+pub mod capture;
+pub mod config;
+pub mod emulator;
+pub mod error;
+pub mod event;
+pub mod firmware;
+pub mod headless;
+pub mod packet;
+pub mod session;
+pub mod station;
+pub mod stats;
+pub mod trace;
+pub mod transport;
 pub mod ui;
 pub mod updater;
+pub mod verify;
 
 
 
@@ -1,15 +1,16 @@
 extern crate serialport;
-mod updater;
-mod ui;
 
 use std::{
     error::Error,
     io,
     io::stdout,
+    path::{Path, PathBuf},
     thread,
     time::{Duration, Instant},
 };
 
+use argh::FromArgs;
+
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
@@ -23,9 +24,301 @@ use tui::{
 
 use cellaxon_base::tool::ticker::Ticker;
 
+use e_drone::system::DeviceType;
+
+use drone_updater::{
+    capture::{Capture, ReplayTransport},
+    config::{parse_device_type, Discovery, UpdaterConfig},
+    firmware,
+    headless::Headless,
+    station::Station,
+    ui,
+    updater,
+    verify,
+};
+
+
+#[derive(FromArgs)]
+/// Drone Updater
+struct Args
+{
+    /// serial port of the device, repeat to update several devices at once (default: wait for a newly connected device)
+    #[argh(option, short = 'p')]
+    port: Vec<String>,
+
+    /// probe every serial port that is already connected
+    #[argh(switch, short = 's')]
+    scan: bool,
+
+    /// baud rate of the serial port (default: 57600)
+    #[argh(option, short = 'b')]
+    baud: Option<u32>,
+
+    /// toml file with baud_rate, firmware_dir, public_key, target and [retry] and [packet] tables; command line options take precedence
+    #[argh(option, short = 'c')]
+    config: Option<PathBuf>,
+
+    /// interval between requests and retransmissions in milliseconds (default: 200)
+    #[argh(option)]
+    interval_ms: Option<u32>,
+
+    /// time to wait for the device to answer an information or location request in milliseconds (default: 1200); the information wait is at least 9 x --interval-ms so every device type is asked once (1800 with the defaults)
+    #[argh(option)]
+    timeout_ms: Option<u32>,
+
+    /// number of retransmissions in a row before giving up (default: 30)
+    #[argh(option)]
+    max_errors: Option<u32>,
+
+    /// time limit for the whole firmware transfer in milliseconds (default: 300000)
+    #[argh(option)]
+    update_limit_ms: Option<u32>,
+
+    /// firmware folder or bundle file (default: 'firmware' folder next to the executable)
+    #[argh(option, short = 'f')]
+    firmware_dir: Option<PathBuf>,
+
+    /// firmware or bundle file to use instead of searching the firmware folder, repeat to give several candidates
+    #[argh(option)]
+    firmware_file: Vec<PathBuf>,
+
+    /// update even if the device already runs the same firmware version
+    #[argh(switch)]
+    force_reflash: bool,
+
+    /// allow updating to a lower firmware version than the device runs
+    #[argh(switch)]
+    allow_downgrade: bool,
+
+    /// device to update when several answer on the port: drone, controller, link-client, link-server, ble-client, ble-server, tester or monitor
+    #[argh(option, from_str_fn(parse_device_type))]
+    target: Option<DeviceType>,
+
+    /// after a device is updated, go on with the next device on the same link (for example the drone behind the controller)
+    #[argh(switch)]
+    chain: bool,
+
+    /// when a device is running its application, restart it into the bootloader and go on with the update
+    #[argh(switch)]
+    reboot_to_bootloader: bool,
+
+    /// keep running: after each device, wait until it is unplugged and update the next one, counting passed and failed units
+    #[argh(switch, long = "loop")]
+    loop_units: bool,
+
+    /// ed25519 public key file; only firmware with a valid '.sig' file or bundle signature is flashed
+    #[argh(option)]
+    public_key: Option<PathBuf>,
+
+    /// flash firmware that has no checksum or signature to check (mismatching files are still refused)
+    #[argh(switch)]
+    allow_unverified: bool,
+
+    /// file that records interrupted updates (default: 'session.toml' next to the executable)
+    #[argh(option)]
+    session_file: Option<PathBuf>,
+
+    /// don't record or resume interrupted updates
+    #[argh(switch)]
+    no_resume: bool,
+
+    /// number of 16 byte blocks in each update packet, 1 to 15 (default: 2)
+    #[argh(option)]
+    blocks_per_packet: Option<u16>,
+
+    /// grow the packet while the device answers cleanly and shrink it on retries
+    #[argh(switch)]
+    adaptive_packet: bool,
+
+    /// upper limit of blocks per packet in adaptive mode (default: 15)
+    #[argh(option)]
+    max_blocks_per_packet: Option<u16>,
+
+    /// number of update packets sent ahead without waiting for the answer (default: 1)
+    #[argh(option)]
+    window: Option<u16>,
+
+    /// write TX/RX frames, decoded data and state changes to a log file per connection (toggle with the T key)
+    #[argh(switch, short = 'd')]
+    debug: bool,
+
+    /// folder for the trace log files (default: 'log' folder next to the executable)
+    #[argh(option)]
+    trace_dir: Option<PathBuf>,
+
+    /// exit when the update is finished instead of waiting for the Esc key
+    #[argh(switch, short = 'x')]
+    exit_on_finish: bool,
+
+    /// run without the terminal screen and print progress as JSON lines
+    #[argh(switch)]
+    headless: bool,
+
+    /// save every byte sent and received to a capture file per connection in this folder
+    #[argh(option)]
+    capture_dir: Option<PathBuf>,
+
+    #[argh(subcommand)]
+    command: Option<Command>,
+}
+
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Command
+{
+    ListFirmware(ListFirmware),
+    Replay(Replay),
+}
+
+
+#[derive(FromArgs)]
+/// List the firmware files the updater would use
+#[argh(subcommand, name = "list-firmware")]
+struct ListFirmware {}
+
+
+#[derive(FromArgs)]
+/// Feed a capture file back into the updater and print progress as JSON lines
+#[argh(subcommand, name = "replay")]
+struct Replay
+{
+    /// capture file saved with --capture-dir
+    #[argh(positional)]
+    path: PathBuf,
+}
+
+
+impl Args
+{
+    fn to_config(&self) -> Result<UpdaterConfig, Box<dyn Error>>
+    {
+        // 반복 모드는 끝나지 않으므로 함께 사용할 수 없음
+        if self.loop_units && self.exit_on_finish
+        {
+            return Err("--loop can't be used with --exit-on-finish".into());
+        }
+
+        let mut config = UpdaterConfig::new();
+
+        if let Some(path) = &self.config
+        {
+            config.apply_file(path)?;
+        }
+
+        config.discovery = match (self.port.first(), self.scan)
+        {
+            (Some(port), _) => Discovery::Port(port.clone()),
+            (None, true) => Discovery::ScanAll,
+            (None, false) => Discovery::Hotplug,
+        };
+        if let Some(baud) = self.baud
+        {
+            config.baud_rate = baud;
+        }
+        if let Some(firmware_dir) = &self.firmware_dir
+        {
+            config.path_firmware = firmware_dir.clone();
+        }
+        config.vec_path_firmware_file = self.firmware_file.clone();
+        config.path_capture = self.capture_dir.clone();
+        config.flag_trace = self.debug;
+        if let Some(trace_dir) = &self.trace_dir
+        {
+            config.path_trace = trace_dir.clone();
+        }
+        config.flag_force_reflash = self.force_reflash;
+        config.flag_allow_downgrade = self.allow_downgrade;
+        if let Some(public_key) = &self.public_key
+        {
+            config.path_public_key = Some(public_key.clone());
+        }
+        config.flag_allow_unverified = self.allow_unverified;
+        if let Some(target) = self.target
+        {
+            config.device_type_target = Some(target);
+        }
+        config.flag_chain = self.chain;
+        config.flag_reboot_bootloader = self.reboot_to_bootloader;
+        config.flag_loop = self.loop_units;
+        config.path_session = match self.no_resume
+        {
+            true => None,
+            false => Some(self.session_file.clone().unwrap_or_else(UpdaterConfig::default_path_session)),
+        };
+
+        if let Some(interval_ms) = self.interval_ms
+        {
+            config.retry.time_interval_ms = interval_ms;
+        }
+        if let Some(timeout_ms) = self.timeout_ms
+        {
+            config.retry.time_information_timeout_ms = timeout_ms;
+            config.retry.time_location_timeout_ms = timeout_ms;
+        }
+        if let Some(max_errors) = self.max_errors
+        {
+            config.retry.count_error_max = max_errors;
+        }
+        if let Some(update_limit_ms) = self.update_limit_ms
+        {
+            config.retry.time_update_limit_ms = update_limit_ms;
+        }
+
+        if let Some(blocks_per_packet) = self.blocks_per_packet
+        {
+            config.packet.count_block = blocks_per_packet;
+        }
+        if let Some(max_blocks_per_packet) = self.max_blocks_per_packet
+        {
+            config.packet.count_block_max = max_blocks_per_packet;
+        }
+        config.packet.flag_adaptive |= self.adaptive_packet;
+        if let Some(window) = self.window
+        {
+            config.packet.count_window = window;
+        }
+
+        // 공개키 파일을 읽을 수 없으면 장치를 찾기 전에 종료
+        if let Some(path_public_key) = &config.path_public_key
+        {
+            verify::read_public_key(path_public_key)?;
+        }
+
+        Ok(config)
+    }
+}
+
 
 fn main() -> Result<(), Box<dyn Error>>
 {
+    let args: Args = argh::from_env();
+    let config = args.to_config()?;
+
+    match &args.command
+    {
+        Some(Command::ListFirmware(_)) => return list_firmware(&config),
+        Some(Command::Replay(replay)) => return run_replay(config, &replay.path),
+        None => {}
+    }
+
+    // 지정된 펌웨어 파일을 읽을 수 없으면 장치를 찾기 전에 종료
+    let vec_file = firmware::load_firmware_files(&config)?;
+
+    if args.headless
+    {
+        if args.port.len() > 1
+        {
+            return Err("Headless mode updates one port at a time".into());
+        }
+
+        let mut updater = updater::Updater::with_firmware(config, vec_file);
+        let sequence = Headless::new(stdout().lock(), &mut updater).run(&mut updater)?;
+
+        // 오류로 끝난 경우 0이 아닌 종료 코드 반환
+        std::process::exit(if sequence.is_error() { 1 } else { 0 });
+    }
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = stdout();
@@ -34,8 +327,20 @@ fn main() -> Result<(), Box<dyn Error>>
     let mut terminal = Terminal::new(backend)?;
 
     // create app and run it
-    let updater = updater::Updater::new();
-    let res = run_app(&mut terminal, updater);
+    let res = if args.port.len() > 1
+    {
+        let station = Station::with_ports(config, vec_file, &args.port);
+        run_station_app(&mut terminal, station, args.exit_on_finish)
+    }
+    else
+    {
+        // 여러 장치가 응답하면 화면에서 업데이트할 장치를 고름
+        let mut config = config;
+        config.flag_select_target = true;
+
+        let updater = updater::Updater::with_firmware(config, vec_file);
+        run_app(&mut terminal, updater, args.exit_on_finish)
+    };
 
     // restore terminal
     disable_raw_mode()?;
@@ -54,7 +359,62 @@ fn main() -> Result<(), Box<dyn Error>>
 }
 
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut updater: updater::Updater) -> io::Result<()>
+// 기록된 수신 데이터로 업데이트 과정을 재현(오류로 끝나면 종료 코드 1)
+fn run_replay(mut config: UpdaterConfig, path: &Path) -> Result<(), Box<dyn Error>>
+{
+    let capture = Capture::read(path)?;
+    let vec_file = firmware::load_firmware_files(&config)?;
+
+    // 재현 결과가 실제 업데이트 기록과 캡처 파일에 남지 않도록 함(기록된 장치 하나만 재현)
+    config.path_session = None;
+    config.path_capture = None;
+    config.flag_loop = false;
+
+    let mut updater = updater::Updater::with_firmware(config, vec_file);
+    updater.connect(Box::new(ReplayTransport::new(&capture)));
+
+    let sequence = Headless::new(stdout().lock(), &mut updater).run(&mut updater)?;
+
+    std::process::exit(if sequence.is_error() { 1 } else { 0 });
+}
+
+
+fn list_firmware(config: &UpdaterConfig) -> Result<(), Box<dyn Error>>
+{
+    let vec_entry = firmware::list_firmware_entries(config)
+        .map_err(|e| format!("Can't read firmware folder {}: {}", config.path_firmware.display(), e))?;
+
+    for entry in vec_entry
+    {
+        match entry.result
+        {
+            Ok(information) =>
+            {
+                println!("{}\t{:?}\t{}\t{} bytes\t{} blocks",
+                    entry.path.display(),
+                    information.model_number,
+                    firmware::format_version(&information.version),
+                    information.size,
+                    information.count_block);
+
+                // 묶음 파일 안의 펌웨어는 목록의 정보를 함께 표시
+                if let Some(manifest) = information.manifest
+                {
+                    println!("\tmin bootloader {}\t{}",
+                        manifest.min_bootloader.as_deref().unwrap_or("-"),
+                        manifest.release_notes);
+                }
+            }
+
+            Err(reason) => println!("{}\tERROR\t{}", entry.path.display(), reason),
+        }
+    }
+
+    Ok(())
+}
+
+
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut updater: updater::Updater, flag_exit_on_finish: bool) -> io::Result<()>
 {
     let mut ticker_ui_update: Ticker = Ticker::new(100);
 
@@ -69,22 +429,90 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut updater: updater::Updater
             terminal.draw(|f| ui::ui(f, &updater))?;
         }
 
-        // 키 입력 처리
-        let last_tick = Instant::now();
-        let tick_rate = Duration::from_millis(1);
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
-        if event::poll(timeout)?
+        // 업데이트가 끝나면 마지막 화면을 그린 후 종료
+        if flag_exit_on_finish && updater.get_sequence().is_finished()
         {
-            if let Event::Key(key) = event::read()?
+            terminal.draw(|f| ui::ui(f, &updater))?;
+            return Ok(());
+        }
+
+        match read_key()?
+        {
+            Some(KeyCode::Esc) => return Ok(()),
+            Some(KeyCode::Char('t')) | Some(KeyCode::Char('T')) =>
             {
-                match key.code
+                let flag_trace = !updater.is_trace_enabled();
+                updater.set_trace(flag_trace);
+            }
+            // 응답한 장치 목록의 번호로 업데이트할 장치 선택
+            Some(KeyCode::Char(c)) if c.is_ascii_digit() =>
+            {
+                let device_type = (c as usize).checked_sub('1' as usize)
+                    .and_then(|index| updater.get_responders().get(index))
+                    .map(|(device_type, _)| *device_type);
+
+                if let Some(device_type) = device_type
                 {
-                    KeyCode::Esc => { return Ok(()); }
-                    _ => {}
+                    updater.select_target(device_type);
                 }
             }
+            _ => {}
         }
     }
 }
+
+
+fn run_station_app<B: Backend>(terminal: &mut Terminal<B>, mut station: Station, flag_exit_on_finish: bool) -> io::Result<()>
+{
+    let mut ticker_ui_update: Ticker = Ticker::new(100);
+
+    loop
+    {
+        thread::sleep(Duration::from_millis(1));
+
+        station.run();
+
+        if ticker_ui_update.check()
+        {
+            terminal.draw(|f| ui::ui_station(f, &station))?;
+        }
+
+        // 모든 슬롯의 업데이트가 끝나면 마지막 화면을 그린 후 종료
+        if flag_exit_on_finish && station.is_finished()
+        {
+            terminal.draw(|f| ui::ui_station(f, &station))?;
+            return Ok(());
+        }
+
+        match read_key()?
+        {
+            Some(KeyCode::Esc) => return Ok(()),
+            Some(KeyCode::Char('t')) | Some(KeyCode::Char('T')) =>
+            {
+                let flag_trace = !station.get_slots().iter().any(|slot| slot.updater.is_trace_enabled());
+                station.get_slots_mut().iter_mut().for_each(|slot| slot.updater.set_trace(flag_trace));
+            }
+            _ => {}
+        }
+    }
+}
+
+
+// 키 입력 처리
+fn read_key() -> io::Result<Option<KeyCode>>
+{
+    let last_tick = Instant::now();
+    let tick_rate = Duration::from_millis(1);
+    let timeout = tick_rate
+        .checked_sub(last_tick.elapsed())
+        .unwrap_or_else(|| Duration::from_secs(0));
+    if event::poll(timeout)?
+    {
+        if let Event::Key(key) = event::read()?
+        {
+            return Ok(Some(key.code));
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,180 @@
+use crate::config::PacketPolicy;
+
+
+// 한 패킷에 담을 수 있는 최대 블럭 수(패킷 길이는 u8, 2 byte 위치 + 16 byte * 15 = 242)
+pub const COUNT_BLOCK_LIMIT: u16 = 15;
+
+// 연속으로 이만큼 응답을 받으면 블럭 수를 늘림
+const COUNT_CLEAN_TO_GROW: u32 = 8;
+
+// 연속으로 이만큼 응답을 받으면 실패했던 크기를 다시 시도
+const COUNT_CLEAN_TO_RAISE_CEILING: u32 = 64;
+
+
+// 펌웨어 전송 시 한 패킷에 담을 블럭 수
+// 적응 모드에서는 응답이 잘 오면 늘리고 재전송이 발생하면 절반으로 줄임
+#[derive(Debug, Clone)]
+pub struct PacketSize
+{
+    flag_adaptive: bool,
+    count_block: u16,           // 현재 패킷당 블럭 수
+    count_block_ceiling: u16,   // 재전송이 발생한 크기보다 하나 작은 값
+    count_block_max: u16,
+    count_clean: u32,           // 재전송 없이 연속으로 받은 응답 수
+}
+
+
+impl PacketSize
+{
+    pub fn new(policy: &PacketPolicy) -> PacketSize
+    {
+        let count_block_max = policy.count_block_max.clamp(1, COUNT_BLOCK_LIMIT);
+
+        PacketSize
+        {
+            flag_adaptive: policy.flag_adaptive,
+            count_block: policy.count_block.clamp(1, count_block_max),
+            count_block_ceiling: count_block_max,
+            count_block_max,
+            count_clean: 0,
+        }
+    }
+
+
+    pub fn get_count_block(&self) -> u16
+    {
+        self.count_block
+    }
+
+
+    // 응답을 받은 경우, 블럭 수가 바뀌면 true
+    pub fn acknowledged(&mut self) -> bool
+    {
+        if !self.flag_adaptive
+        {
+            return false;
+        }
+
+        self.count_clean += 1;
+
+        if self.count_clean.is_multiple_of(COUNT_CLEAN_TO_RAISE_CEILING) && self.count_block_ceiling < self.count_block_max
+        {
+            self.count_block_ceiling += 1;
+        }
+
+        if self.count_clean.is_multiple_of(COUNT_CLEAN_TO_GROW) && self.count_block < self.count_block_ceiling
+        {
+            self.count_block += 1;
+            return true;
+        }
+
+        false
+    }
+
+
+    // 재전송하는 경우, 블럭 수가 바뀌면 true
+    pub fn retried(&mut self) -> bool
+    {
+        if !self.flag_adaptive
+        {
+            return false;
+        }
+
+        self.count_clean = 0;
+
+        if self.count_block > 1
+        {
+            self.count_block_ceiling = self.count_block - 1;
+            self.count_block = (self.count_block / 2).max(1);
+            return true;
+        }
+
+        false
+    }
+}
+
+
+
+// 응답을 기다리지 않고 연속으로 보낼 수 있는 패킷 수
+// 장치가 따라오지 못해 빠진 패킷이 생기면 하나씩 보내는 방식으로 돌아간 후 다시 늘림
+#[derive(Debug, Clone)]
+pub struct Window
+{
+    count_window: u16,          // 현재 동시에 보낼 수 있는 패킷 수
+    count_window_max: u16,
+    count_clean: u32,           // 빠진 패킷 없이 연속으로 받은 응답 수
+}
+
+
+impl Window
+{
+    pub fn new(count_window_max: u16) -> Window
+    {
+        let count_window_max = count_window_max.max(1);
+
+        Window
+        {
+            count_window: count_window_max,
+            count_window_max,
+            count_clean: 0,
+        }
+    }
+
+
+    pub fn get_count_window(&self) -> u16
+    {
+        self.count_window
+    }
+
+
+    pub fn acknowledged(&mut self)
+    {
+        self.count_clean += 1;
+
+        if self.count_clean.is_multiple_of(COUNT_CLEAN_TO_GROW) && self.count_window < self.count_window_max
+        {
+            self.count_window += 1;
+        }
+    }
+
+
+    pub fn gap(&mut self)
+    {
+        self.count_clean = 0;
+        self.count_window = 1;
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn grows_while_clean_and_backs_off_on_retry()
+    {
+        let mut packet_size = PacketSize::new(&PacketPolicy{ count_block: 2, count_block_max: 6, flag_adaptive: true, count_window: 1 });
+
+        for _ in 0..COUNT_CLEAN_TO_GROW * 10
+        {
+            packet_size.acknowledged();
+        }
+        assert_eq!(packet_size.get_count_block(), 6);
+
+        assert!(packet_size.retried());
+        assert_eq!(packet_size.get_count_block(), 3);
+
+        // 실패한 크기는 한동안 다시 시도하지 않음
+        for _ in 0..COUNT_CLEAN_TO_GROW * 4
+        {
+            packet_size.acknowledged();
+        }
+        assert_eq!(packet_size.get_count_block(), 5);
+
+        let mut packet_size = PacketSize::new(&PacketPolicy{ count_block: 40, count_block_max: 40, flag_adaptive: false, count_window: 1 });
+        assert!(!packet_size.acknowledged());
+        assert!(!packet_size.retried());
+        assert_eq!(packet_size.get_count_block(), COUNT_BLOCK_LIMIT);
+    }
+}
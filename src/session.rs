@@ -0,0 +1,287 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use e_drone::system::{DeviceType, ModelNumber};
+
+
+// 이 시간 안에 기록된 경우 아직 업데이트가 진행 중인 것으로 봄(진행 중에는 1초마다 기록)
+const TIME_RECORD_ACTIVE_MS: u64 = 5000;
+
+
+// 진행 중인 업데이트 기록(프로그램이 중간에 종료되어도 다음 실행 시 이어서 업데이트하기 위함)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord
+{
+    pub port: String,               // 포트 이름
+    pub device_type: u8,            // 장치 종류
+    pub model_number: u32,          // 장치 모델 번호
+    pub firmware_hash: String,      // 전송 중인 펌웨어 파일의 SHA-256
+    pub index_block: u16,           // 장치가 마지막으로 받은 블럭 위치
+    #[serde(default)]
+    pub time_saved: u64,            // 기록한 시각(UNIX 시간, ms)
+}
+
+
+impl SessionRecord
+{
+    pub fn is_same_device(&self, port: &str, device_type: DeviceType, model_number: ModelNumber) -> bool
+    {
+        self.port == port && self.is_same_model(device_type, model_number)
+    }
+
+
+    pub fn is_same_model(&self, device_type: DeviceType, model_number: ModelNumber) -> bool
+    {
+        self.device_type == u8::from(device_type) &&
+        self.model_number == u32::from(model_number)
+    }
+
+
+    // 다른 포트에서 아직 업데이트를 진행하며 기록하고 있는지 확인
+    pub fn is_active(&self) -> bool
+    {
+        get_time_now_ms().saturating_sub(self.time_saved) < TIME_RECORD_ACTIVE_MS
+    }
+}
+
+
+pub fn get_time_now_ms() -> u64
+{
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+
+#[derive(Default, Serialize, Deserialize)]
+struct SessionFile
+{
+    #[serde(default)]
+    session: Vec<SessionRecord>,
+}
+
+
+// 세션 기록 파일(여러 포트의 기록을 한 파일에 보관)
+#[derive(Debug, Clone)]
+pub struct SessionStore
+{
+    path: PathBuf,
+}
+
+
+impl SessionStore
+{
+    pub fn new(path: &Path) -> SessionStore
+    {
+        SessionStore
+        {
+            path: path.to_path_buf(),
+        }
+    }
+
+
+    pub fn get_path(&self) -> &Path
+    {
+        &self.path
+    }
+
+
+    pub fn find(&self, port: &str, device_type: DeviceType, model_number: ModelNumber) -> Option<SessionRecord>
+    {
+        self.load()
+            .session
+            .into_iter()
+            .find(|record| record.is_same_device(port, device_type, model_number))
+    }
+
+
+    // 중단된 장치의 기록
+    // 같은 포트의 기록이 없으면 포트 이름이 바뀐 경우로 보고 다른 포트의 기록 중에서
+    // 기록이 멈췄고 장치가 알려온 위치보다 앞서지 않은 기록을 위치가 가까운 순으로 선택
+    pub fn find_interrupted(&self, port: &str, device_type: DeviceType, model_number: ModelNumber, index_block: u16) -> Option<SessionRecord>
+    {
+        let vec_record: Vec<SessionRecord> = self.load()
+            .session
+            .into_iter()
+            .filter(|record| record.is_same_model(device_type, model_number))
+            .collect();
+
+        if let Some(record) = vec_record.iter().find(|record| record.port == port)
+        {
+            return Some(record.clone());
+        }
+
+        vec_record
+            .into_iter()
+            .filter(|record| !record.is_active() && record.index_block <= index_block)
+            .max_by_key(|record| record.index_block)
+    }
+
+
+    // 같은 장치의 기록이 있으면 교체
+    pub fn save(&self, record: &SessionRecord) -> io::Result<()>
+    {
+        let mut file = self.load();
+
+        file.session.retain(|r| r.port != record.port || r.device_type != record.device_type || r.model_number != record.model_number);
+        file.session.push(record.clone());
+
+        self.store(&file)
+    }
+
+
+    pub fn remove(&self, port: &str, device_type: DeviceType, model_number: ModelNumber) -> io::Result<()>
+    {
+        let mut file = self.load();
+        let count = file.session.len();
+
+        file.session.retain(|record| !record.is_same_device(port, device_type, model_number));
+
+        if file.session.len() != count
+        {
+            self.store(&file)?;
+        }
+
+        Ok(())
+    }
+
+
+    // 파일이 없거나 읽을 수 없으면 빈 기록으로 처리
+    fn load(&self) -> SessionFile
+    {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+
+    // 쓰는 도중 종료되어도 기존 파일이 깨지지 않도록 임시 파일에 쓴 후 교체
+    fn store(&self, file: &SessionFile) -> io::Result<()>
+    {
+        let text = toml::to_string(file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut path_temp = self.path.clone().into_os_string();
+        path_temp.push(".tmp");
+
+        fs::write(&path_temp, text)?;
+        fs::rename(&path_temp, &self.path)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use std::{sync::Arc, time::Duration};
+
+    use e_drone::system::{ModeUpdate, Version};
+
+    use crate::emulator;
+    use crate::updater::Sequence;
+
+    // 블럭 20까지 받은 상태로 중단된 장치에 다시 연결(장치는 channel-a에 연결, flag_active이면 기록이 아직 갱신 중)
+    fn resume(name: &str, port: &str, flag_same_file: bool, index_block: u16, flag_active: bool) -> (Sequence, bool, SessionStore)
+    {
+        let path = std::env::temp_dir().join(format!("drone_updater_session_{}_{}.toml", name, std::process::id()));
+        let store = SessionStore::new(&path);
+
+        let vec_file = Arc::new(vec![emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 3 }, 16 * 40)]);
+        let eb = &vec_file[0];
+        let vec_expected = eb.data_array.clone();
+
+        store.save(&SessionRecord
+        {
+            port: String::from(port),
+            device_type: DeviceType::Drone.into(),
+            model_number: ModelNumber::Drone3DroneP5.into(),
+            firmware_hash: if flag_same_file { crate::firmware::hash_firmware(eb) } else { String::from("0123") },
+            index_block,
+            time_saved: if flag_active { get_time_now_ms() } else { 0 },
+        }).unwrap();
+
+        let mut config = emulator::config_unverified();
+        config.path_session = Some(path);
+
+        let (mut updater, mut emulator) = emulator::connect(config, vec_file.clone(), DeviceType::Drone, ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 1, build: 1 }, ModeUpdate::Ready);
+        emulator.set_partial(eb, 20);
+
+        let sequence = emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10));
+
+        (sequence, emulator.get_image() == &vec_expected[..], store)
+    }
+
+    #[test]
+    fn same_firmware_resumes_and_clears_record()
+    {
+        let (sequence, flag_image_match, store) = resume("same", "channel-a", true, 20, false);
+
+        assert_eq!(sequence, Sequence::UpdateComplete);
+        assert!(flag_image_match);
+        assert!(store.find("channel-a", DeviceType::Drone, ModelNumber::Drone3DroneP5).is_none());
+
+        let _ = fs::remove_file(store.get_path());
+
+        // 포트 이름이 바뀐 경우에도 기록을 찾아 이어서 진행
+        let (sequence, flag_image_match, store) = resume("renamed", "COM3", true, 16, false);
+
+        assert_eq!(sequence, Sequence::UpdateComplete);
+        assert!(flag_image_match);
+        assert!(store.find("COM3", DeviceType::Drone, ModelNumber::Drone3DroneP5).is_none());
+
+        let _ = fs::remove_file(store.get_path());
+    }
+
+    #[test]
+    fn different_firmware_is_refused()
+    {
+        let (sequence, _, store) = resume("different", "channel-a", false, 20, false);
+
+        assert_eq!(sequence, Sequence::ResumeRefused);
+        assert!(store.find("channel-a", DeviceType::Drone, ModelNumber::Drone3DroneP5).is_some());
+
+        let _ = fs::remove_file(store.get_path());
+
+        // 포트 이름이 바뀌어도 다른 펌웨어 파일이면 거부
+        let (sequence, _, store) = resume("different_renamed", "COM3", false, 20, false);
+        assert_eq!(sequence, Sequence::ResumeRefused);
+        let _ = fs::remove_file(store.get_path());
+
+        // 기록된 위치가 장치가 알려온 위치보다 앞선 경우
+        let (sequence, _, store) = resume("ahead", "channel-a", true, 30, false);
+        assert_eq!(sequence, Sequence::ResumeMismatch);
+        let _ = fs::remove_file(store.get_path());
+    }
+
+    #[test]
+    fn other_port_record_is_kept()
+    {
+        // 다른 포트에서 진행 중인 기록은 넘겨받지 않음
+        let (sequence, flag_image_match, store) = resume("active", "COM3", false, 20, true);
+
+        assert_eq!(sequence, Sequence::UpdateComplete);
+        assert!(flag_image_match);
+        assert!(store.find("COM3", DeviceType::Drone, ModelNumber::Drone3DroneP5).is_some());
+
+        let _ = fs::remove_file(store.get_path());
+
+        // 장치가 알려온 위치보다 앞선 다른 포트의 기록은 다른 장치의 기록
+        let (sequence, flag_image_match, store) = resume("other_ahead", "COM3", true, 30, false);
+
+        assert_eq!(sequence, Sequence::UpdateComplete);
+        assert!(flag_image_match);
+        assert!(store.find("COM3", DeviceType::Drone, ModelNumber::Drone3DroneP5).is_some());
+
+        let _ = fs::remove_file(store.get_path());
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use e_drone::file::EncryptedBinary;
+
+use crate::config::{Discovery, UpdaterConfig};
+use crate::updater::Updater;
+
+
+// 하나의 포트에서 동작하는 업데이트 세션
+pub struct Slot
+{
+    pub name: String,
+    pub updater: Updater,
+}
+
+
+// 여러 포트의 업데이트를 동시에 진행(펌웨어 파일 목록은 모든 슬롯이 공유)
+pub struct Station
+{
+    vec_slot: Vec<Slot>,
+}
+
+
+impl Station
+{
+    pub fn new() -> Station
+    {
+        Station
+        {
+            vec_slot: Vec::new(),
+        }
+    }
+
+
+    // 포트마다 슬롯을 하나씩 생성
+    pub fn with_ports(config: UpdaterConfig, vec_file: impl Into<Arc<Vec<EncryptedBinary>>>, vec_port_name: &[String]) -> Station
+    {
+        let vec_file = vec_file.into();
+
+        let mut station = Station::new();
+
+        for port_name in vec_port_name
+        {
+            let mut config_slot = config.clone();
+            config_slot.discovery = Discovery::Port(port_name.clone());
+
+            station.add_slot(port_name, Updater::with_firmware(config_slot, vec_file.clone()));
+        }
+
+        station
+    }
+
+
+    pub fn add_slot(&mut self, name: &str, updater: Updater)
+    {
+        self.vec_slot.push(Slot
+        {
+            name: name.to_string(),
+            updater,
+        });
+    }
+
+
+    pub fn get_slots(&self) -> &[Slot]
+    {
+        &self.vec_slot
+    }
+
+
+    pub fn get_slots_mut(&mut self) -> &mut [Slot]
+    {
+        &mut self.vec_slot
+    }
+
+
+    // 모든 슬롯을 차례로 한 번씩 실행
+    pub fn run(&mut self)
+    {
+        for slot in self.vec_slot.iter_mut()
+        {
+            slot.updater.run();
+        }
+    }
+
+
+    pub fn is_finished(&self) -> bool
+    {
+        self.vec_slot.iter().all(|slot| slot.updater.get_sequence().is_finished())
+    }
+}
+
+
+impl Default for Station
+{
+    fn default() -> Self
+    {
+        Station::new()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use e_drone::system::{DeviceType, ModeUpdate, ModelNumber, Version};
+
+    use crate::emulator::{self, Emulator};
+    use crate::transport::ChannelTransport;
+    use crate::updater::Sequence;
+
+    #[test]
+    fn slots_share_firmware_and_update_together()
+    {
+        let version_old = Version{ major: 22, minor: 0, build: 1 };
+        let version_new = Version{ major: 22, minor: 1, build: 0 };
+
+        let eb_drone = emulator::build_firmware(ModelNumber::Drone3DroneP5, version_new, 16 * 30);
+        let eb_controller = emulator::build_firmware(ModelNumber::Drone3ControllerP4, version_new, 16 * 20);
+        let vec_expected = [eb_drone.data_array.clone(), eb_controller.data_array.clone()];
+        let vec_file = Arc::new(vec![eb_drone, eb_controller]);
+
+        let mut station = Station::new();
+        let mut vec_emulator = Vec::new();
+
+        for (name, device_type, model_number) in [("slot-1", DeviceType::Drone, ModelNumber::Drone3DroneP5), ("slot-2", DeviceType::Controller, ModelNumber::Drone3ControllerP4)]
+        {
+            let (transport_updater, transport_device) = ChannelTransport::pair();
+
+            let mut updater = Updater::with_firmware(emulator::config_unverified(), vec_file.clone());
+            updater.connect(Box::new(transport_updater));
+            station.add_slot(name, updater);
+
+            vec_emulator.push(Emulator::new(Box::new(transport_device), device_type, model_number, version_old, ModeUpdate::Ready));
+        }
+
+        let time_start = Instant::now();
+        while !station.is_finished() && time_start.elapsed() < Duration::from_secs(10)
+        {
+            station.run();
+            vec_emulator.iter_mut().for_each(|emulator| emulator.run());
+            thread::sleep(Duration::from_micros(200));
+        }
+
+        for (slot, (emulator, expected)) in station.get_slots().iter().zip(vec_emulator.iter().zip(vec_expected.iter()))
+        {
+            assert_eq!(*slot.updater.get_sequence(), Sequence::UpdateComplete, "{}", slot.name);
+            assert_eq!(emulator.get_image(), &expected[..]);
+        }
+
+        assert_eq!(Arc::strong_count(&vec_file), 3);
+    }
+}
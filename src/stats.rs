@@ -0,0 +1,226 @@
+use std::time::{Duration, Instant};
+
+use crate::updater::Sequence;
+
+
+// 전송 속도를 다시 계산하는 최소 간격
+const TIME_SAMPLE_MS: u128 = 250;
+
+// 새로 측정한 전송 속도의 반영 비율
+const RATIO_SMOOTHING: f32 = 0.3;
+
+
+// 펌웨어 전송 통계
+#[derive(Debug, Clone)]
+pub struct TransferStats
+{
+    pub count_block_sent: u32,          // 전송한 블럭 수(재전송 포함)
+    pub count_byte_sent: u64,           // 전송한 펌웨어 데이터 크기(재전송 포함)
+    pub count_block_acknowledged: u32,  // 이번 연결에서 장치가 받았다고 알려온 블럭 수
+    pub count_byte_acknowledged: u64,
+    pub count_retransmission: u32,      // 재전송 횟수
+    pub count_block_per_packet: u16,    // 현재 패킷당 블럭 수
+    pub count_window: u16,              // 현재 연속으로 보낼 수 있는 패킷 수
+    pub index_block_next: u16,          // 장치가 다음에 받을 블럭 위치
+    pub index_block_end: u16,           // 전체 블럭 수
+    pub throughput: f32,                // 평활화한 전송 속도(byte/s, 장치가 받은 데이터 기준)
+
+    vec_time_phase: Vec<(Sequence, Duration)>,  // 끝난 단계별 소요 시간
+    sequence: Sequence,
+    time_phase_start: Instant,
+
+    time_sample: Option<Instant>,       // 전송 속도 측정 시작 시각
+    count_byte_sample: u64,             // 측정 시작 시점의 count_byte_acknowledged
+}
+
+
+impl TransferStats
+{
+    pub fn new() -> TransferStats
+    {
+        TransferStats
+        {
+            count_block_sent: 0,
+            count_byte_sent: 0,
+            count_block_acknowledged: 0,
+            count_byte_acknowledged: 0,
+            count_retransmission: 0,
+            count_block_per_packet: 0,
+            count_window: 0,
+            index_block_next: 0,
+            index_block_end: 0,
+            throughput: 0.0,
+
+            vec_time_phase: Vec::new(),
+            sequence: Sequence::LookUpNewDevice,
+            time_phase_start: Instant::now(),
+
+            time_sample: None,
+            count_byte_sample: 0,
+        }
+    }
+
+
+    // 단계가 바뀔 때 이전 단계의 시간을 누적
+    pub fn set_sequence(&mut self, sequence: Sequence)
+    {
+        let time_elapsed = self.time_phase_start.elapsed();
+
+        match self.vec_time_phase.iter_mut().find(|(s, _)| *s == self.sequence)
+        {
+            Some((_, time)) => *time += time_elapsed,
+            None => self.vec_time_phase.push((self.sequence, time_elapsed)),
+        }
+
+        self.sequence = sequence;
+        self.time_phase_start = Instant::now();
+    }
+
+
+    pub fn start_transfer(&mut self, index_block_next: u16, index_block_end: u16)
+    {
+        self.index_block_next = index_block_next;
+        self.index_block_end = index_block_end;
+        self.throughput = 0.0;
+        self.time_sample = None;
+        self.count_byte_sample = self.count_byte_acknowledged;
+    }
+
+
+    pub fn sent(&mut self, count_byte: usize)
+    {
+        self.count_block_sent += ((count_byte + 15) >> 4) as u32;
+        self.count_byte_sent += count_byte as u64;
+    }
+
+
+    pub fn retransmitted(&mut self)
+    {
+        self.count_retransmission += 1;
+    }
+
+
+    // 장치가 알려온 위치가 앞으로 이동한 경우
+    pub fn acknowledged(&mut self, index_block_next: u16)
+    {
+        let count_block = index_block_next.saturating_sub(self.index_block_next) as u32;
+
+        self.index_block_next = index_block_next;
+        self.count_block_acknowledged += count_block;
+        self.count_byte_acknowledged += (count_block as u64) << 4;
+
+        let time_sample = match self.time_sample
+        {
+            Some(time_sample) => time_sample,
+            None =>
+            {
+                self.time_sample = Some(Instant::now());
+                self.count_byte_sample = self.count_byte_acknowledged;
+                return;
+            }
+        };
+
+        let time_elapsed = time_sample.elapsed();
+        if time_elapsed.as_millis() < TIME_SAMPLE_MS
+        {
+            return;
+        }
+
+        let throughput = (self.count_byte_acknowledged - self.count_byte_sample) as f32 / time_elapsed.as_secs_f32();
+
+        self.throughput = match self.throughput > 0.0
+        {
+            true => self.throughput + (throughput - self.throughput) * RATIO_SMOOTHING,
+            false => throughput,
+        };
+
+        self.time_sample = Some(Instant::now());
+        self.count_byte_sample = self.count_byte_acknowledged;
+    }
+
+
+    // 진행률(0 ~ 100)
+    pub fn get_percent(&self) -> f32
+    {
+        match self.index_block_end
+        {
+            0 => 0.0,
+            index_block_end => (self.index_block_next.min(index_block_end) as f32 * 100.0) / index_block_end as f32,
+        }
+    }
+
+
+    // 남은 데이터와 평활화한 전송 속도로 계산한 남은 시간(아직 측정 전이면 None)
+    pub fn get_eta(&self) -> Option<Duration>
+    {
+        if self.throughput <= 0.0
+        {
+            return None;
+        }
+
+        let count_byte_left = (self.index_block_end.saturating_sub(self.index_block_next) as u64) << 4;
+
+        Some(Duration::from_secs_f32(count_byte_left as f32 / self.throughput))
+    }
+
+
+    // 단계별 소요 시간(진행 중인 단계 포함)
+    pub fn get_time_phase(&self, sequence: Sequence) -> Duration
+    {
+        let time_finished = self.vec_time_phase
+            .iter()
+            .filter(|(s, _)| *s == sequence)
+            .map(|(_, time)| *time)
+            .sum();
+
+        match sequence == self.sequence
+        {
+            true => time_finished + self.time_phase_start.elapsed(),
+            false => time_finished,
+        }
+    }
+}
+
+
+impl Default for TransferStats
+{
+    fn default() -> Self
+    {
+        TransferStats::new()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn eta_follows_smoothed_throughput()
+    {
+        let mut stats = TransferStats::new();
+        stats.set_sequence(Sequence::FirmwareUpdate);
+        stats.start_transfer(0, 100);
+
+        assert_eq!(stats.get_percent(), 0.0);
+        assert_eq!(stats.get_eta(), None);
+
+        stats.acknowledged(10);
+        thread::sleep(Duration::from_millis(300));
+        stats.acknowledged(50);
+
+        assert_eq!(stats.count_block_acknowledged, 50);
+        assert_eq!(stats.get_percent(), 50.0);
+
+        // 0.3초 동안 40블럭(640 byte)을 받았으므로 남은 50블럭은 약 0.4초
+        let eta = stats.get_eta().unwrap();
+        assert!(eta > Duration::from_millis(200) && eta < Duration::from_millis(500), "{:?}", eta);
+
+        stats.set_sequence(Sequence::VerifyUpdate);
+        assert!(stats.get_time_phase(Sequence::FirmwareUpdate) >= Duration::from_millis(300));
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{LineWriter, Write},
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+
+// 통신 내용과 상태 변화를 연결마다 하나의 파일로 기록
+// 화면(TUI)을 사용하는 중에도 출력이 섞이지 않도록 파일에만 기록하며 실행 중에 켜고 끌 수 있음
+pub struct Trace
+{
+    path_dir: PathBuf,                  // 기록 파일을 만들 폴더
+    flag_enabled: bool,
+    name_session: String,               // 기록 파일 이름에 들어갈 연결 시각과 포트 이름
+    time_start: Instant,
+    path_file: Option<PathBuf>,
+    file: Option<LineWriter<File>>,     // 처음 기록할 때 생성
+}
+
+
+impl Trace
+{
+    pub fn new(path_dir: &Path, flag_enabled: bool) -> Trace
+    {
+        Trace
+        {
+            path_dir: path_dir.to_path_buf(),
+            flag_enabled,
+            name_session: String::from("session"),
+            time_start: Instant::now(),
+            path_file: None,
+            file: None,
+        }
+    }
+
+
+    pub fn set_enabled(&mut self, flag_enabled: bool)
+    {
+        if !flag_enabled
+        {
+            self.write("TRACE", format_args!("off"));
+        }
+
+        self.flag_enabled = flag_enabled;
+
+        if flag_enabled
+        {
+            self.write("TRACE", format_args!("on"));
+        }
+    }
+
+
+    pub fn is_enabled(&self) -> bool
+    {
+        self.flag_enabled
+    }
+
+
+    // 현재 기록 중인 파일
+    pub fn get_path(&self) -> Option<&Path>
+    {
+        self.path_file.as_deref()
+    }
+
+
+    // 새로운 연결의 기록 시작(파일은 처음 기록할 때 생성)
+    pub fn start(&mut self, port: &str)
+    {
+        self.name_session = name_session(port);
+        self.time_start = Instant::now();
+        self.path_file = None;
+        self.file = None;
+    }
+
+
+    pub fn tx(&mut self, slice_data: &[u8])
+    {
+        if self.flag_enabled
+        {
+            self.write("TX", format_args!("{}", format_hex(slice_data)));
+        }
+    }
+
+
+    pub fn rx(&mut self, slice_data: &[u8])
+    {
+        if self.flag_enabled
+        {
+            self.write("RX", format_args!("{}", format_hex(slice_data)));
+        }
+    }
+
+
+    // 해석한 데이터, 상태 변경 등
+    pub fn event(&mut self, kind: &str, value: &dyn fmt::Debug)
+    {
+        if self.flag_enabled
+        {
+            self.write(kind, format_args!("{:?}", value));
+        }
+    }
+
+
+    // 기록에 실패해도 업데이트는 계속 진행
+    fn write(&mut self, kind: &str, args: fmt::Arguments)
+    {
+        if !self.flag_enabled
+        {
+            return;
+        }
+
+        if self.file.is_none()
+        {
+            let path_file = self.path_dir.join(format!("trace_{}.log", self.name_session));
+
+            let file = fs::create_dir_all(&self.path_dir).and_then(|_| File::options().create(true).append(true).open(&path_file));
+            match file
+            {
+                Ok(file) =>
+                {
+                    self.file = Some(LineWriter::new(file));
+                    self.path_file = Some(path_file);
+                }
+                Err(_) =>
+                {
+                    self.flag_enabled = false;
+                    return;
+                }
+            }
+        }
+
+        if let Some(file) = &mut self.file
+        {
+            let time_elapsed = self.time_start.elapsed();
+            let _ = writeln!(file, "{:>6}.{:06} {:<5} {}", time_elapsed.as_secs(), time_elapsed.subsec_micros(), kind, args);
+        }
+    }
+}
+
+
+// 파일 이름에 사용할 연결 이름(unix time(ms)_포트 이름)
+pub fn name_session(port: &str) -> String
+{
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_millis()).unwrap_or(0);
+    let port: String = port
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+
+    format!("{}_{}", time, port)
+}
+
+
+pub fn format_hex(slice_data: &[u8]) -> String
+{
+    slice_data
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn writes_only_while_enabled()
+    {
+        let path_dir = std::env::temp_dir().join(format!("drone_updater_trace_{}", std::process::id()));
+
+        let mut trace = Trace::new(&path_dir, false);
+        trace.start("COM3");
+        trace.tx(&[0x0A, 0x55]);
+        assert!(trace.get_path().is_none());
+
+        trace.set_enabled(true);
+        trace.tx(&[0x0A, 0x55, 0x90]);
+        trace.event("STATE", &"FirmwareUpdate");
+        trace.set_enabled(false);
+        trace.rx(&[0x0A]);
+
+        let path_file = trace.get_path().unwrap().to_path_buf();
+        let text = fs::read_to_string(&path_file).unwrap();
+        fs::remove_dir_all(&path_dir).unwrap();
+
+        assert!(path_file.file_name().unwrap().to_string_lossy().ends_with("_COM3.log"));
+
+        let vec_line: Vec<&str> = text.lines().collect();
+        assert_eq!(vec_line.len(), 4);
+        assert!(vec_line[1].ends_with("TX    0A 55 90"));
+        assert!(vec_line[2].ends_with("STATE \"FirmwareUpdate\""));
+        assert!(vec_line[3].ends_with("TRACE off"));
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::VecDeque,
+    io,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    time::Duration,
+};
+
+use serialport::SerialPort;
+
+
+// Updater가 장치와 데이터를 주고 받는 통로
+pub trait Transport: Send
+{
+    // 프레임 데이터 전송
+    fn write(&mut self, slice_data: &[u8]) -> io::Result<usize>;
+
+    // 수신된 데이터를 buffer에 복사(수신된 데이터가 없으면 0 반환)
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize>;
+
+    // 연결 종료
+    fn close(&mut self);
+
+    // 화면 표시 및 기록용 연결 정보
+    fn describe(&self) -> String;
+}
+
+
+// -- SerialTransport -------------------------------------------------------------------------------------------
+pub struct SerialTransport
+{
+    port: Option<Box<dyn SerialPort>>,
+    port_name: String,
+    baud_rate: u32,
+}
+
+
+impl SerialTransport
+{
+    pub fn open(port_name: &str, baud_rate: u32) -> serialport::Result<SerialTransport>
+    {
+        let port = serialport::new(port_name, baud_rate)
+            .timeout(Duration::from_millis(1))
+            .open()?;
+
+        Ok(SerialTransport
+        {
+            port: Some(port),
+            port_name: port_name.to_string(),
+            baud_rate,
+        })
+    }
+
+
+    pub fn get_port_name(&self) -> &str
+    {
+        &self.port_name
+    }
+}
+
+
+impl Transport for SerialTransport
+{
+    fn write(&mut self, slice_data: &[u8]) -> io::Result<usize>
+    {
+        match &mut self.port
+        {
+            Some(port) => port.write(slice_data),
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "Port closed")),
+        }
+    }
+
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize>
+    {
+        match &mut self.port
+        {
+            Some(port) =>
+            {
+                match port.read(buffer)
+                {
+                    // 읽기 제한 시간 초과는 수신된 데이터가 없는 것으로 처리
+                    Err(e) if e.kind() == io::ErrorKind::TimedOut => Ok(0),
+                    result => result,
+                }
+            }
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "Port closed")),
+        }
+    }
+
+
+    fn close(&mut self)
+    {
+        self.port = None;
+    }
+
+
+    fn describe(&self) -> String
+    {
+        format!("{} ({} bps)", self.port_name, self.baud_rate)
+    }
+}
+
+
+// -- ChannelTransport -------------------------------------------------------------------------------------------
+// 메모리 상에서 서로 연결된 한 쌍의 통로(테스트 및 시리얼 포트 외의 연결에 사용)
+pub struct ChannelTransport
+{
+    name: String,
+    tx: Option<Sender<Vec<u8>>>,
+    rx: Receiver<Vec<u8>>,
+    queue_buffer: VecDeque<u8>,
+}
+
+
+impl ChannelTransport
+{
+    pub fn pair() -> (ChannelTransport, ChannelTransport)
+    {
+        let (tx_a, rx_b) = mpsc::channel();
+        let (tx_b, rx_a) = mpsc::channel();
+
+        (
+            ChannelTransport{ name: String::from("channel-a"), tx: Some(tx_a), rx: rx_a, queue_buffer: VecDeque::new() },
+            ChannelTransport{ name: String::from("channel-b"), tx: Some(tx_b), rx: rx_b, queue_buffer: VecDeque::new() },
+        )
+    }
+
+
+    pub fn set_name(&mut self, name: &str)
+    {
+        self.name = name.to_string();
+    }
+}
+
+
+impl Transport for ChannelTransport
+{
+    fn write(&mut self, slice_data: &[u8]) -> io::Result<usize>
+    {
+        match &self.tx
+        {
+            Some(tx) =>
+            {
+                match tx.send(slice_data.to_vec())
+                {
+                    Ok(()) => Ok(slice_data.len()),
+                    Err(_) => Err(io::Error::new(io::ErrorKind::BrokenPipe, "Channel disconnected")),
+                }
+            }
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "Channel closed")),
+        }
+    }
+
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize>
+    {
+        loop
+        {
+            match self.rx.try_recv()
+            {
+                Ok(vec_data) => self.queue_buffer.extend(vec_data),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) =>
+                {
+                    if self.queue_buffer.is_empty()
+                    {
+                        return Err(io::Error::new(io::ErrorKind::BrokenPipe, "Channel disconnected"));
+                    }
+                    break;
+                }
+            }
+        }
+
+        let length = std::cmp::min(buffer.len(), self.queue_buffer.len());
+        for (b, q) in buffer.iter_mut().zip(self.queue_buffer.drain(..length))
+        {
+            *b = q;
+        }
+
+        Ok(length)
+    }
+
+
+    fn close(&mut self)
+    {
+        self.tx = None;
+    }
+
+
+    fn describe(&self) -> String
+    {
+        self.name.clone()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn channel_pair_delivers_bytes_in_order()
+    {
+        let (mut a, mut b) = ChannelTransport::pair();
+        let mut buffer = [0u8; 4];
+
+        assert_eq!(b.read(&mut buffer).unwrap(), 0);
+
+        a.write(&[1, 2, 3]).unwrap();
+        a.write(&[4, 5]).unwrap();
+
+        assert_eq!(b.read(&mut buffer).unwrap(), 4);
+        assert_eq!(buffer, [1, 2, 3, 4]);
+        assert_eq!(b.read(&mut buffer).unwrap(), 1);
+        assert_eq!(buffer[0], 5);
+
+        a.close();
+        assert!(a.write(&[6]).is_err());
+        assert!(b.read(&mut buffer).is_err());
+    }
+}
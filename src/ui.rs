@@ -1,11 +1,15 @@
-use crate::updater::Updater;
+use crate::station::Station;
+use crate::stats::TransferStats;
+use crate::firmware::format_version;
+use crate::updater::{Sequence, Updater};
 
 use tui::{
     backend::{Backend},
     layout::{Alignment, Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
+    text::Span,
     widgets::{
-        Gauge, Paragraph, Wrap,
+        Block, Borders, Gauge, Paragraph, Wrap,
     },
     Frame,
 };
@@ -30,9 +34,10 @@ pub fn ui<B: Backend>(f: &mut Frame<B>, updater: &Updater)
         )
         .split(f.size());
 
-    let (time_total, time_progress, time_left, progress) = updater.get_update_information();
+    let stats = updater.get_transfer_stats();
+    let progress = stats.get_percent();
 
-    let paragraph = Paragraph::new("Drone Updater")
+    let paragraph = Paragraph::new(format!("Drone Updater{}", format_counts(updater)))
         .style(Style::default().fg(Color::White))
         .alignment(Alignment::Center)
         .wrap(Wrap { trim: true });
@@ -52,13 +57,174 @@ pub fn ui<B: Backend>(f: &mut Frame<B>, updater: &Updater)
                 .add_modifier(Modifier::ITALIC | Modifier::BOLD),
         )
         .ratio((progress / 100_f32) as f64)
-        .label(format!("{}%", format!("{}", progress)));
+        .label(format!("{:.1}%", progress));
     f.render_widget(gauge, chunks[3]);
 
-    let paragraph = Paragraph::new(updater.get_message_status())
+    let paragraph = Paragraph::new(format_stats(stats))
+        .style(Style::default().fg(Color::Gray))
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, chunks[4]);
+
+    // 여러 장치가 응답한 경우 번호와 함께 장치 목록 표시
+    let (message_status, message_key) = match updater.get_sequence()
+    {
+        Sequence::SelectTarget => (format_responders(updater), "1-9: select device   "),
+        _ => (updater.get_message_status().to_string(), ""),
+    };
+
+    // 연속 업데이트에서 끝난 장치별 결과
+    let message_status = match updater.get_device_results().is_empty()
+    {
+        true => message_status,
+        false => format!("{}\n{}", message_status, format_device_results(updater)),
+    };
+
+    let paragraph = Paragraph::new(message_status)
         .style(Style::default().fg(Color::White))
         .alignment(Alignment::Center)
         .wrap(Wrap { trim: true });
     f.render_widget(paragraph, chunks[5]);
+
+    let paragraph = Paragraph::new(format!("Esc: exit   {}T: {}", message_key, format_trace(updater)))
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, chunks[6]);
+}
+
+
+
+// 여러 슬롯의 업데이트 상태를 한 줄씩 표시
+pub fn ui_station<B: Backend>(f: &mut Frame<B>, station: &Station)
+{
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(2), Constraint::Min(0)].as_ref())
+        .split(f.size());
+
+    let flag_trace = station.get_slots().iter().any(|slot| slot.updater.is_trace_enabled());
+    let paragraph = Paragraph::new(format!("Drone Updater   (Esc: exit   T: trace {})", if flag_trace { "on" } else { "off" }))
+        .style(Style::default().fg(Color::White))
+        .alignment(Alignment::Center);
+    f.render_widget(paragraph, chunks[0]);
+
+    let vec_constraint: Vec<Constraint> = station.get_slots().iter().map(|_| Constraint::Length(4)).collect();
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec_constraint)
+        .split(chunks[1]);
+
+    for (slot, row) in station.get_slots().iter().zip(rows.iter())
+    {
+        let updater = &slot.updater;
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(Span::raw(format!("{}{}", slot.name, format_counts(updater))));
+        let area = block.inner(*row);
+        f.render_widget(block, *row);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(40), Constraint::Percentage(30)].as_ref())
+            .split(area);
+
+        let paragraph = Paragraph::new(format!("{:?} {}", updater.get_information().model_number, updater.get_message_version()))
+            .style(Style::default().fg(Color::White));
+        f.render_widget(paragraph, columns[0]);
+
+        let stats = updater.get_transfer_stats();
+        let progress = stats.get_percent();
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(Color::Green).bg(Color::DarkGray))
+            .ratio((progress / 100_f32) as f64)
+            .label(format!("{:.1}%  {:.1} KB/s  acked {:.1} KB", progress, stats.throughput / 1024.0, stats.count_byte_acknowledged as f32 / 1024.0));
+        f.render_widget(gauge, columns[1]);
+
+        let color = if updater.get_sequence().is_error() { Color::Red } else { Color::White };
+        let paragraph = Paragraph::new(format!(" {}\n {}", updater.get_message_status(), format_phases(stats)))
+            .style(Style::default().fg(color));
+        f.render_widget(paragraph, columns[2]);
+    }
 }
 
+
+// 전송 속도, 남은 시간, 재전송 횟수, 패킷 크기
+fn format_stats(stats: &TransferStats) -> String
+{
+    let eta = match stats.get_eta()
+    {
+        Some(eta) => format!("{:02}:{:02}", eta.as_secs() / 60, eta.as_secs() % 60),
+        None => String::from("--:--"),
+    };
+
+    format!("{:.1} KB/s   ETA {}   sent {:.1} KB   acked {:.1} KB   retry {}   {} block x {}   {}",
+        stats.throughput / 1024.0,
+        eta,
+        stats.count_byte_sent as f32 / 1024.0,
+        stats.count_byte_acknowledged as f32 / 1024.0,
+        stats.count_retransmission,
+        stats.count_block_per_packet,
+        stats.count_window,
+        format_phases(stats))
+}
+
+
+// 전송, 확인 단계에 걸린 시간
+fn format_phases(stats: &TransferStats) -> String
+{
+    format!("transfer {:.1} s   verify {:.1} s",
+        stats.get_time_phase(Sequence::FirmwareUpdate).as_secs_f32(),
+        stats.get_time_phase(Sequence::VerifyUpdate).as_secs_f32())
+}
+
+
+fn format_responders(updater: &Updater) -> String
+{
+    updater.get_responders()
+        .iter()
+        .enumerate()
+        .map(|(index, (device_type, information))| format!("{}: {:?} {:?} {}",
+            index + 1,
+            device_type,
+            information.model_number,
+            format_version(&information.version)))
+        .collect::<Vec<String>>()
+        .join("   ")
+}
+
+
+fn format_device_results(updater: &Updater) -> String
+{
+    updater.get_device_results()
+        .iter()
+        .map(|result| format!("{:?}: {:?}", result.device_type, result.sequence))
+        .collect::<Vec<String>>()
+        .join("   ")
+}
+
+
+// 반복 모드에서 성공, 실패한 장치 수
+fn format_counts(updater: &Updater) -> String
+{
+    let (count_pass, count_fail) = updater.get_counts();
+
+    match updater.is_loop_enabled()
+    {
+        true => format!("   passed {}   failed {}", count_pass, count_fail),
+        false => String::new(),
+    }
+}
+
+
+fn format_trace(updater: &Updater) -> String
+{
+    match (updater.is_trace_enabled(), updater.get_trace_path())
+    {
+        (false, _) => String::from("trace off"),
+        (true, Some(path)) => format!("trace on ({})", path.display()),
+        (true, None) => String::from("trace on"),
+    }
+}
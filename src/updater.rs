@@ -1,13 +1,13 @@
-#[warn(unreachable_code)]
 extern crate serialport;
 
 use std::{
-    time::{Duration, Instant},
-    fs,
+    cmp::Ordering,
+    time::Instant,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, mpsc::{self, Sender}},
 };
 
-use serialport::{*};
-
 use cellaxon_base::tool::ticker::Ticker;
 
 use e_drone::file::EncryptedBinary;
@@ -15,16 +15,35 @@ use e_drone::communication::{*};
 use e_drone::communication::receiver::{*};
 use e_drone::system::{*};
 use e_drone::protocol::{*};
+use e_drone::protocol::command::CommandType;
 use e_drone::{*};
 
+use crate::capture::{self, RecordingTransport};
+use crate::config::{Discovery, UpdaterConfig};
+use crate::error::{ErrorContext, UpdateError};
+use crate::event::Event;
+use crate::firmware;
+use crate::packet::{PacketSize, Window};
+use crate::session::{self, SessionRecord, SessionStore};
+use crate::stats::TransferStats;
+use crate::trace::{self, Trace};
+use crate::transport::{SerialTransport, Transport};
+use crate::verify::{self, Verification};
+
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Sequence
 {
     LookUpNewDevice,            // 새로 연결된 장치를 검색하고 새로운 장치가 있는 경우 연결
     CheckDeviceInformation,     // 연결된 장치의 정보 요청
+    SelectTarget,               // 여러 장치가 응답하여 업데이트할 장치를 고를 때까지 기다림
+    RebootToBootloader,         // 애플리케이션 모드의 장치를 부트로더로 다시 시작하고 응답할 때까지 기다림
     CheckUpdateLocation,        // 연결된 장치의 업데이트 정보 요청
     FirmwareUpdate,             // 펌웨어 업데이트
+    VerifyUpdate,               // 장치가 다시 시작된 후 설치된 펌웨어 확인
+    WaitForRemoval,             // 반복 모드에서 결과를 표시하고 장치가 분리될 때까지 기다림
     UpdateComplete,             // 업데이트 완료 처리
+    AlreadyUpToDate,            // 장치의 펌웨어 버전이 파일과 같아 업데이트하지 않음
 
     // Error State
     NoFirmwareFile,     // 펌웨어 파일이 없음
@@ -32,27 +51,95 @@ pub enum Sequence
     NoMatchedFirmwareFile,      // 장치와 일치하는 펌웨어 파일이 없음
     ApplicationMode,    // 장치가 애플리케이션 모드로 동작 중(부트로더 모드로 다시 연결 필요)
     UpdateTimeOver,     // 업데이트 제한 시간 초과
+    DowngradeRefused,   // 장치의 펌웨어 버전이 파일보다 높아 업데이트하지 않음
+    ResumeRefused,      // 중단된 업데이트를 다른 펌웨어 파일로 이어서 진행할 수 없음
+    ResumeMismatch,     // 기록된 위치가 장치가 알려온 위치보다 앞서 이어서 진행할 수 없음
+    VerifyFailed,       // 업데이트 후 장치의 모델 번호 또는 버전이 펌웨어 파일과 다름
+    FirmwareRejected,   // 펌웨어 파일의 SHA-256 또는 서명을 확인할 수 없음
+}
+
+
+impl Sequence
+{
+    // 더 이상 진행할 단계가 없는 상태인지 확인
+    pub fn is_finished(&self) -> bool
+    {
+        !matches!(self,
+            Sequence::LookUpNewDevice |
+            Sequence::CheckDeviceInformation |
+            Sequence::SelectTarget |
+            Sequence::RebootToBootloader |
+            Sequence::CheckUpdateLocation |
+            Sequence::FirmwareUpdate |
+            Sequence::VerifyUpdate |
+            Sequence::WaitForRemoval)
+    }
+
+
+    // 오류로 끝난 상태인지 확인
+    pub fn is_error(&self) -> bool
+    {
+        self.is_finished() && *self != Sequence::UpdateComplete && *self != Sequence::AlreadyUpToDate
+    }
+}
+
+
+// 장치 정보를 요청하는 장치 종류 수
+const COUNT_DEVICE_TYPE_PROBE: u128 = 8;
+
+
+// 연속 업데이트(flag_chain)에 사용하는 장치 순서(다른 장치를 연결해 주는 장치를 먼저 업데이트)
+const LIST_DEVICE_TYPE_CHAIN: [DeviceType; 8] = [
+    DeviceType::Controller,
+    DeviceType::LinkServer,
+    DeviceType::LinkClient,
+    DeviceType::BleServer,
+    DeviceType::BleClient,
+    DeviceType::Tester,
+    DeviceType::Monitor,
+    DeviceType::Drone,
+];
+
+
+// 연속 업데이트에서 끝난 장치의 결과
+#[derive(Debug, Clone)]
+pub struct DeviceResult
+{
+    pub device_type: DeviceType,
+    pub model_number: ModelNumber,
+    pub version: Version,           // 업데이트 전 장치의 펌웨어 버전
+    pub sequence: Sequence,         // 마지막 상태
 }
 
 
 pub struct Updater
 {
+    config: UpdaterConfig,
+
     sequence: Sequence,
 
-    vec_file: Vec<EncryptedBinary>,
+    vec_file: Arc<Vec<EncryptedBinary>>,   // 여러 Updater가 같은 펌웨어 파일 목록을 공유할 수 있음
     vec_serialport: Vec<String>,    // 시작 시 시리얼포트 목록
+    vec_serialport_scan: Vec<String>,   // 전체 검색 시 아직 확인하지 않은 시리얼포트 목록
 
     ticker_transfer: Ticker,
 
-    port: Result<Box<dyn SerialPort>>,
+    port: Option<Box<dyn Transport>>,
+    port_name: String,
+    flag_port_external: bool,       // 외부에서 연결해 준 통로인 경우 새로운 장치 검색을 하지 않음
     buffer: [u8; 1024],
     receiver: Receiver,
     data: Data,
 
     device_type_target: DeviceType,
     information_target: Information,
+    vec_responder: Vec<(DeviceType, Information)>,  // 장치 정보 요청에 응답한 장치 목록
+    device_type_select: Option<DeviceType>,         // 업데이트할 장치(정해지면 이후 요청은 이 장치에만 보냄)
+    vec_device_result: Vec<DeviceResult>,           // 연속 업데이트에서 끝난 장치 목록
+    flag_chain_finished: bool,                      // 연속 업데이트할 장치가 더 없음
     update_location: UpdateLocation,
     index_target_vec_file: usize,
+    verification: Option<Verification>,     // 선택한 펌웨어 파일의 확인 결과
     index_block_end: u16,
     message_status: String,
     message_version: String,
@@ -60,12 +147,35 @@ pub struct Updater
     flag_transfer_success: bool,
     count_error: u32,
 
-    flag_show_debug_message: bool,  // 디버깅 정보 표시
+    trace: Trace,                   // 통신 기록
     
     time_transfer: Instant,
     time_receive: Instant,
 
     time_sequence_start: Instant,
+    time_connect: Instant,          // 장치 연결 시각
+
+    error: Option<UpdateError>,     // 오류로 종료된 경우 오류 정보
+    error_io: Option<Arc<io::Error>>,   // 마지막으로 발생한 통신 오류
+
+    vec_event_sender: Vec<Sender<Event>>,
+
+    session: Option<SessionStore>,  // 진행 중인 업데이트 기록
+    hash_firmware: String,          // 전송 중인 펌웨어 파일의 SHA-256
+    time_session_save: Instant,
+
+    information_verify: Option<Information>,    // 업데이트 후 장치가 알려온 정보
+
+    packet_size: PacketSize,        // 패킷당 블럭 수
+    window: Window,                 // 연속으로 보낼 수 있는 패킷 수
+    index_block_send: u16,          // 다음에 보낼 블럭 위치(연속 전송 시 장치가 받은 위치보다 앞섬)
+    index_block_tick: u16,          // 이전 주기에 장치가 알려온 위치
+    flag_rewound: bool,             // 빠진 패킷 위치부터 다시 보내는 중
+
+    stats: TransferStats,           // 전송 통계
+
+    count_pass: u32,                // 성공한 장치 수
+    count_fail: u32,                // 실패한 장치 수
 }
 
 
@@ -73,16 +183,48 @@ impl Updater
 {
     pub fn new() -> Updater 
     {
+        // 기본 설정은 펌웨어 폴더 안에서 읽을 수 있는 파일만 사용
+        let config = UpdaterConfig::new();
+        let vec_file = firmware::lookup_firmware_files(&config.path_firmware);
+
+        Updater::with_firmware(config, vec_file)
+    }
+
+
+    // 지정된 파일 또는 묶음 파일을 읽을 수 없으면 오류
+    pub fn with_config(config: UpdaterConfig) -> Result<Updater, String>
+    {
+        let vec_file = firmware::load_firmware_files(&config)?;
+
+        Ok(Updater::with_firmware(config, vec_file))
+    }
+
+
+    // 이미 읽어둔 펌웨어 파일 목록을 사용
+    pub fn with_firmware(config: UpdaterConfig, vec_file: impl Into<Arc<Vec<EncryptedBinary>>>) -> Updater
+    {
+        let ticker_transfer = Ticker::new(config.retry.time_interval_ms);
+        let packet_size = PacketSize::new(&config.packet);
+        let window = Window::new(config.packet.count_window);
+        let device_type_select = config.device_type_target;
+
         let mut updater = Updater
         {
+            trace: Trace::new(&config.path_trace, config.flag_trace),
+
+            config,
+
             sequence: Sequence::LookUpNewDevice,
 
-            vec_file: Updater::lookup_firmware_files(),
+            vec_file: vec_file.into(),
             vec_serialport: Vec::new(),
+            vec_serialport_scan: Vec::new(),
 
-            ticker_transfer: Ticker::new(200),
+            ticker_transfer,
 
-            port: Err(serialport::Error{kind: serialport::ErrorKind::NoDevice, description: "Not Connected".to_string()}),
+            port: None,
+            port_name: String::new(),
+            flag_port_external: false,
             buffer: [0u8; 1024],
             receiver: Receiver::new(),
             data: Data::None,
@@ -90,7 +232,12 @@ impl Updater
             device_type_target: DeviceType::None,
             information_target: Information::new(),
             update_location: UpdateLocation::new(),
+            vec_responder: Vec::new(),
+            device_type_select,
+            vec_device_result: Vec::new(),
+            flag_chain_finished: false,
             index_target_vec_file: 0,
+            verification: None,
             index_block_end: 0,
             message_status: String::new(),
             message_version: String::new(),
@@ -98,19 +245,42 @@ impl Updater
             flag_transfer_success: true,
             count_error: 0,
 
-            flag_show_debug_message: false,
-
             time_transfer: Instant::now(),
             time_receive: Instant::now(),
 
             time_sequence_start: Instant::now(),
+            time_connect: Instant::now(),
+
+            error: None,
+            error_io: None,
+
+            vec_event_sender: Vec::new(),
+
+            session: None,
+            hash_firmware: String::new(),
+            time_session_save: Instant::now(),
+
+            information_verify: None,
+
+            packet_size,
+            window,
+            index_block_send: 0,
+            index_block_tick: 0,
+            flag_rewound: false,
+
+            stats: TransferStats::new(),
+
+            count_pass: 0,
+            count_fail: 0,
         };
 
+        updater.session = updater.config.path_session.as_deref().map(SessionStore::new);
+
         // 시리얼 포트 목록 생성
         updater.create_port_list();
         
         
-        if updater.is_exists_firmware_file() == false
+        if !updater.is_exists_firmware_file()
         {
             updater.set_sequence(Sequence::NoFirmwareFile);
         }
@@ -133,9 +303,119 @@ impl Updater
     }
 
 
+    // 외부에서 준비한 통로로 장치에 연결하고 장치 정보 확인 모드로 변경
+    pub fn connect(&mut self, transport: Box<dyn Transport>)
+    {
+        self.disconnect();
+
+        self.emit(Event::DeviceConnected{ port: transport.describe() });
+
+        self.port_name = transport.describe();
+        self.port = Some(self.wrap_capture(transport));
+        self.flag_port_external = true;
+        self.time_connect = Instant::now();
+        self.error_io = None;
+        self.stats = TransferStats::new();
+        self.trace.start(&self.port_name);
+
+        if self.is_exists_firmware_file()
+        {
+            self.set_sequence(Sequence::CheckDeviceInformation);
+        }
+    }
+
+
+    // 설정된 경우 주고 받는 데이터를 연결마다 하나의 파일로 저장
+    fn wrap_capture(&self, transport: Box<dyn Transport>) -> Box<dyn Transport>
+    {
+        let path_dir = match &self.config.path_capture
+        {
+            Some(path_dir) => path_dir,
+            None => return transport,
+        };
+
+        let path = path_dir.join(format!("capture_{}.txt", trace::name_session(&transport.describe())));
+
+        match capture::create_file(&path)
+        {
+            Ok(writer) => Box::new(RecordingTransport::new(transport, writer)),
+            Err(_) => transport,
+        }
+    }
+
+
+    // 이벤트를 받을 채널 생성
+    pub fn subscribe(&mut self) -> mpsc::Receiver<Event>
+    {
+        let (tx, rx) = mpsc::channel();
+        self.vec_event_sender.push(tx);
+        rx
+    }
+
+
+    // 구독 중인 모든 채널에 이벤트 전달(수신측이 사라진 채널은 제거)
+    fn emit(&mut self, event: Event)
+    {
+        self.vec_event_sender.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+
+
+    fn disconnect(&mut self)
+    {
+        if let Some(port) = &mut self.port
+        {
+            port.close();
+        }
+
+        self.port = None;
+        self.receiver.clear_all();
+    }
+
+
+    // 실행 중에 통신 기록을 켜고 끔
+    pub fn set_trace(&mut self, flag_enabled: bool)
+    {
+        self.trace.set_enabled(flag_enabled);
+    }
+
+
+    pub fn is_trace_enabled(&self) -> bool
+    {
+        self.trace.is_enabled()
+    }
+
+
+    pub fn get_trace_path(&self) -> Option<&Path>
+    {
+        self.trace.get_path()
+    }
+
+
+    pub fn get_port_description(&self) -> Option<String>
+    {
+        self.port.as_ref().map(|port| port.describe())
+    }
+
+
+    // 응답이 없는 경우 다시 장치를 찾거나 외부 통로로 정보 요청을 다시 시작
+    fn restart_lookup(&mut self)
+    {
+        if self.flag_port_external
+        {
+            self.set_sequence(Sequence::CheckDeviceInformation);
+        }
+        else
+        {
+            self.disconnect();
+            self.create_port_list();
+            self.set_sequence(Sequence::LookUpNewDevice);
+        }
+    }
+
+
     fn is_exists_firmware_file(&self) -> bool
     {
-        self.vec_file.len() > 0
+        !self.vec_file.is_empty()
     }
 
 
@@ -156,19 +436,88 @@ impl Updater
         &self.sequence
     }
 
+
+    pub fn get_device_type(&self) -> DeviceType
+    {
+        self.device_type_target
+    }
+
+
+    pub fn get_information(&self) -> &Information
+    {
+        &self.information_target
+    }
+
+
+    pub fn get_error(&self) -> Option<&UpdateError>
+    {
+        self.error.as_ref()
+    }
+
+
+    fn get_error_context(&self) -> ErrorContext
+    {
+        ErrorContext
+        {
+            port: self.get_port_description(),
+            device_type: self.device_type_target,
+            model_number: self.information_target.model_number,
+            index_block: self.update_location.index_block_next,
+            count_retry: self.stats.count_retransmission,
+            time_elapsed: self.time_connect.elapsed(),
+            error_io: self.error_io.clone(),
+        }
+    }
+
     
     fn set_sequence(&mut self, sequence: Sequence)
     {
+        let sequence = self.chain_sequence(sequence);
+
         match sequence
         {
             Sequence::FirmwareUpdate =>
             {
+                self.packet_size = PacketSize::new(&self.config.packet);
+                self.window = Window::new(self.config.packet.count_window);
+                self.index_block_send = self.update_location.index_block_next;
+                self.index_block_tick = self.update_location.index_block_next;
+                self.flag_rewound = false;
                 self.flag_transfer_success = true;
+                self.stats.start_transfer(self.update_location.index_block_next, self.index_block_end);
+            }
+
+            Sequence::CheckDeviceInformation =>
+            {
+                self.vec_responder.clear();
+            }
+
+            Sequence::SelectTarget =>
+            {
+                self.message_status = String::from("Several devices answered, select the one to update");
+            }
+
+            Sequence::RebootToBootloader =>
+            {
+                self.message_status = String::from("Restarting device in bootloader mode");
+            }
+
+            Sequence::VerifyUpdate =>
+            {
+                self.message_status = String::from("Verifying installed firmware");
+                self.information_verify = None;
+            }
+
+            // 앞 장치의 결과를 그대로 표시
+            Sequence::WaitForRemoval =>
+            {
+                self.message_status = format!("{}, remove the device", self.message_status);
             }
 
             Sequence::UpdateComplete =>
             {
                 self.message_status = String::from("Update Complete");
+                self.remove_session();
             }
 
             Sequence::NoFirmwareFile =>
@@ -186,23 +535,134 @@ impl Updater
                 self.message_status = String::from("Reconnect with bootloader mode");
             }
 
+            Sequence::NoAnswer =>
+            {
+                self.message_status = String::from("No answer from device");
+            }
+
+            Sequence::UpdateTimeOver =>
+            {
+                self.message_status = String::from("Update time over");
+            }
+
+            Sequence::AlreadyUpToDate =>
+            {
+                self.message_status = String::from("Already up to date");
+            }
+
+            Sequence::DowngradeRefused =>
+            {
+                self.message_status = String::from("Downgrade refused");
+            }
+
+            Sequence::ResumeRefused =>
+            {
+                self.message_status = String::from("Can't resume with a different firmware file");
+            }
+
+            Sequence::ResumeMismatch =>
+            {
+                self.message_status = String::from("Device is behind the interrupted update");
+            }
+
+            Sequence::VerifyFailed =>
+            {
+                self.message_status = String::from("Installed firmware doesn't match");
+            }
+
+            Sequence::FirmwareRejected =>
+            {
+                self.message_status = String::from("Firmware file rejected");
+            }
+
             _ => {}
         }
 
-        self.ticker_transfer = Ticker::new(200); // sequence 변경 시 ticker 내부의 카운터를 초기화하기 위함
+        self.error = match sequence
+        {
+            Sequence::NoFirmwareFile => Some(UpdateError::NoFirmwareFile{ path: self.config.vec_path_firmware_file.first().unwrap_or(&self.config.path_firmware).clone() }),
+            Sequence::NoAnswer => Some(UpdateError::NoAnswer(self.get_error_context())),
+            Sequence::NoMatchedFirmwareFile => Some(UpdateError::NoMatchedFirmwareFile(self.get_error_context())),
+            Sequence::ApplicationMode => Some(UpdateError::ApplicationMode(self.get_error_context())),
+            Sequence::UpdateTimeOver => Some(UpdateError::UpdateTimeOver(self.get_error_context())),
+            Sequence::DowngradeRefused => Some(UpdateError::DowngradeRefused
+            {
+                context: self.get_error_context(),
+                version_device: self.information_target.version,
+                version_firmware: self.vec_file[self.index_target_vec_file].header.version,
+            }),
+            Sequence::ResumeRefused => Some(UpdateError::ResumeRefused
+            {
+                context: self.get_error_context(),
+                path_session: self.session.as_ref().map(|session| session.get_path().to_path_buf()).unwrap_or_default(),
+            }),
+            Sequence::ResumeMismatch => Some(UpdateError::ResumeMismatch
+            {
+                context: self.get_error_context(),
+                path_session: self.session.as_ref().map(|session| session.get_path().to_path_buf()).unwrap_or_default(),
+            }),
+            Sequence::VerifyFailed => Some(UpdateError::VerifyFailed
+            {
+                context: self.get_error_context(),
+                model_number_firmware: self.vec_file[self.index_target_vec_file].header.model_number,
+                version_firmware: self.vec_file[self.index_target_vec_file].header.version,
+                version_device: self.information_verify.map(|information| information.version),
+            }),
+            Sequence::FirmwareRejected => Some(UpdateError::FirmwareRejected
+            {
+                context: self.get_error_context(),
+                path: PathBuf::from(&self.vec_file[self.index_target_vec_file].file_name),
+                verification: self.verification.clone().unwrap_or(Verification::Unverified(String::new())),
+            }),
+            _ => None,
+        };
+
+        self.ticker_transfer = Ticker::new(self.config.retry.time_interval_ms); // sequence 변경 시 ticker 내부의 카운터를 초기화하기 위함
         self.time_sequence_start = Instant::now();
         self.sequence = sequence;
+        self.stats.set_sequence(sequence);
+        self.trace.event("STATE", &sequence);
+
+        self.emit(Event::StateChanged{ sequence });
+
+        if self.config.flag_loop && sequence.is_finished() && sequence != Sequence::NoFirmwareFile
+        {
+            match sequence.is_error()
+            {
+                true => self.count_fail += 1,
+                false => self.count_pass += 1,
+            }
+        }
+
+        if sequence == Sequence::UpdateComplete
+        {
+            self.emit(Event::Completed);
+        }
+        else if let Some(error) = self.error.clone()
+        {
+            self.trace.event("ERROR", &error.to_string());
+            self.emit(Event::Failed{ error });
+        }
     }
 
     
     fn send(&mut self, slice_data: &[u8]) -> bool
     {
-        if let Ok(port) = &mut self.port
+        if let Some(port) = &mut self.port
         {
-            if let Ok(_len) = port.write(slice_data)
+            self.trace.tx(slice_data);
+
+            match port.write(slice_data)
             {
-                self.time_transfer = Instant::now();
-                return true;
+                Ok(_len) =>
+                {
+                    self.time_transfer = Instant::now();
+                    return true;
+                }
+                Err(e) =>
+                {
+                    self.error_io = Some(Arc::new(e));
+                }
             }
         }
 
@@ -218,18 +678,21 @@ impl Updater
 
     fn check(&mut self) -> &Data
     {
-        if let Ok(port) = &mut self.port
+        if let Some(port) = &mut self.port
         {
-            if let Ok(length_read) = &port.read(&mut self.buffer)
+            match port.read(&mut self.buffer)
             {
-                if *length_read > 0
+                Ok(length_read) =>
                 {
-                    if self.flag_show_debug_message 
+                    if length_read > 0
                     {
-                        println!("RX: {:X?}", &self.buffer[..*length_read]);
+                        self.trace.rx(&self.buffer[..length_read]);
+                        self.receiver.push_slice(&self.buffer[..length_read]);
                     }
-
-                    self.receiver.push_slice(&self.buffer[..*length_read]);
+                }
+                Err(e) =>
+                {
+                    self.error_io = Some(Arc::new(e));
                 }
             }
 
@@ -238,6 +701,7 @@ impl Updater
                 self.receiver.clear();
                 self.time_receive = Instant::now();
                 self.data = handler::check(self.receiver.get_header(), self.receiver.get_data());
+                self.trace.event("DATA", &self.data);
                 return &self.data;
             }
         }
@@ -261,6 +725,16 @@ impl Updater
                 self.run_check_device_information();
             }
 
+            Sequence::SelectTarget =>
+            {
+                // 사용자가 select_target으로 장치를 고를 때까지 기다림
+            }
+
+            Sequence::RebootToBootloader =>
+            {
+                self.run_reboot_to_bootloader();
+            }
+
             Sequence::CheckUpdateLocation =>
             {
                 self.run_check_update_location();
@@ -270,60 +744,32 @@ impl Updater
             {
                 self.run_firmware_update();
             }
+
+            Sequence::VerifyUpdate =>
+            {
+                self.run_verify_update();
+            }
             
 
+            Sequence::WaitForRemoval =>
+            {
+                self.run_wait_for_removal();
+            }
+
             Sequence::NoFirmwareFile =>
             {
                 
             }
 
-            _ => {}
-        }
-
-    }
-
-
-    fn lookup_firmware_files() -> Vec<EncryptedBinary>
-    {
-        let mut vec_file: Vec<EncryptedBinary> = Vec::new();
-
-        // find firmware files
-        if let Ok(mut path) = std::env::current_exe()
-        {
-            path.pop();
-            path.push("firmware");
-
-            let paths = fs::read_dir(path);
-
-            if let Ok(p) = paths
+            // 반복 모드에서는 결과가 나오면 장치가 분리될 때까지 기다림
+            sequence if sequence.is_finished() && self.config.flag_loop =>
             {
-                for file in p
-                {
-                    if let Ok(f) = file
-                    {
-                        if let Some(fpath) = f.path().to_str()
-                        {
-                            let mut eb = EncryptedBinary::new();
-
-                            if eb.read(fpath) {
-                                /*
-                                // 읽은 펌웨어 파일의 정보 표시
-                                tx_message_status.send(String::from(
-                                    "{} / Name: {}",
-                                    vec_file.len(),
-                                    f.path().display()
-                                );
-                                tx_message_status.send(String::from("{:?}", eb.header);
-                                */
-                                vec_file.push(eb);
-                            }
-                        }
-                    }
-                }
+                self.set_sequence(Sequence::WaitForRemoval);
             }
+
+            _ => {}
         }
 
-        vec_file
     }
 
 
@@ -342,11 +788,11 @@ impl Updater
             {
                 let mut vec_serialport_clone = vec_serialport_new.clone();
 
-                for i in 0..vec_serialport.len()
+                for port_name in vec_serialport.iter()
                 {
                     for j in 0..vec_serialport_clone.len()
                     {
-                        if vec_serialport[i] == vec_serialport_clone[j]
+                        if *port_name == vec_serialport_clone[j]
                         {
                             vec_serialport_clone.remove(j);
                             break;
@@ -356,7 +802,7 @@ impl Updater
 
                 *vec_serialport = vec_serialport_new;
 
-                if vec_serialport_clone.len() > 0
+                if !vec_serialport_clone.is_empty()
                 {
                     return Some(vec_serialport_clone[0].clone());
                 }
@@ -367,95 +813,329 @@ impl Updater
             }
         }
 
-        return None;
+        None
     }
 
 
-    fn run_look_up_new_device(&mut self)
+    // 현재 연결되어 있는 전체 포트 목록(앞쪽 포트부터 확인하도록 역순으로 정렬)
+    fn lookup_all_devices() -> Vec<String>
     {
-        if let Some(port_name) =  Updater::lookup_new_device(&mut self.vec_serialport)
+        let mut vec_serialport: Vec<String> = Vec::new();
+
+        if let Ok(vec_sp_info) = serialport::available_ports()
         {
-            self.port = serialport::new(port_name, 57_600)
-                .timeout(Duration::from_millis(1))
-                .open();
-            
-            if let Ok(_port) = &mut self.port
-            {
-                // 시리얼 포트가 정상적으로 열린 경우 장치 정보 확인 모드로 변경
-                self.set_sequence(Sequence::CheckDeviceInformation);
-            }
-            else
+            for sp_info in vec_sp_info
             {
-                // 연결 할 수 없는 장치 이름을 기존 장치 이름 목록에 넣고 새로운 장치 검색 모드로 복귀
-                self.create_port_list();
-                self.set_sequence(Sequence::LookUpNewDevice);
+                vec_serialport.push(sp_info.port_name);
             }
         }
+
+        vec_serialport.reverse();
+        vec_serialport
     }
 
 
-    fn run_check_device_information(&mut self)
+    // 설정된 장치 검색 방식에 따라 다음에 열어 볼 포트 이름
+    fn next_port_name(&mut self) -> Option<String>
     {
-        // 주기적으로 information 데이터를 요청
-        if self.ticker_transfer.check()
+        match &self.config.discovery
         {
-            let mut device_type = DeviceType::Drone;
+            Discovery::Hotplug => Updater::lookup_new_device(&mut self.vec_serialport),
 
-            match self.ticker_transfer.get_count() % 8
+            // 지정된 포트가 있는 경우 주기적으로 연결 시도
+            Discovery::Port(port_name) =>
             {
-                1 => { device_type = DeviceType::Controller; }
-                2 => { device_type = DeviceType::LinkClient; }
-                3 => { device_type = DeviceType::LinkServer; }
-                4 => { device_type = DeviceType::BleClient; }
-                5 => { device_type = DeviceType::BleServer; }
-                6 => { device_type = DeviceType::Tester; }
-                7 => { device_type = DeviceType::Monitor; }
-                _ => {}
+                if self.ticker_transfer.check() { Some(port_name.clone()) } else { None }
             }
 
-            self.request(device_type, DataType::Information);
-        }
+            // 확인할 포트 목록이 비어 있으면 주기적으로 다시 채움
+            Discovery::ScanAll =>
+            {
+                if self.vec_serialport_scan.is_empty() && self.ticker_transfer.check()
+                {
+                    self.vec_serialport_scan = Updater::lookup_all_devices();
+                }
 
-        if let Data::Information(information) = self.check()
+                self.vec_serialport_scan.pop()
+            }
+        }
+    }
+
+
+    fn open_port(&mut self, port_name: String) -> bool
+    {
+        self.disconnect();
+
+        match SerialTransport::open(&port_name, self.config.baud_rate)
         {
-            self.information_target = *information;
-            self.device_type_target = self.receiver.get_header().from;
+            Ok(transport) =>
+            {
+                self.emit(Event::DeviceConnected{ port: transport.describe() });
+                self.port_name = port_name;
+                self.port = Some(self.wrap_capture(Box::new(transport)));
+                self.flag_port_external = false;
+                self.error_io = None;
+                true
+            }
+            Err(e) =>
+            {
+                // 포트를 열 수 없는 이유를 오류 정보에 남김
+                self.error_io = Some(Arc::new(io::Error::from(e)));
+                false
+            }
+        }
+    }
+
+
+    fn run_look_up_new_device(&mut self)
+    {
+        // 외부 통로를 사용하는 경우 connect로 새 통로를 받을 때까지 시리얼 포트를 검색하지 않음
+        if self.flag_port_external
+        {
+            return;
+        }
 
-            if self.flag_show_debug_message
+        if let Some(port_name) = self.next_port_name()
+        {
+            if self.open_port(port_name)
             {
-                println!("Received Information: {:?}", self.information_target);
+                // 시리얼 포트가 정상적으로 열린 경우 장치 정보 확인 모드로 변경
+                self.time_connect = Instant::now();
+                self.stats = TransferStats::new();
+                self.trace.start(&self.port_name);
+                self.set_sequence(Sequence::CheckDeviceInformation);
             }
+            else
+            {
+                // 연결 할 수 없는 장치 이름을 기존 장치 이름 목록에 넣고 새로운 장치 검색 모드로 복귀
+                self.create_port_list();
+                self.set_sequence(Sequence::LookUpNewDevice);
+            }
+        }
+    }
+
 
-            if  self.information_target.model_number != ModelNumber::None 
+    fn run_check_device_information(&mut self)
+    {
+        // 주기적으로 information 데이터를 요청(업데이트할 장치가 정해진 경우 그 장치에만 요청)
+        if self.ticker_transfer.check()
+        {
+            let mut device_type = DeviceType::Drone;
+
+            match self.ticker_transfer.get_count() % COUNT_DEVICE_TYPE_PROBE
             {
-                if self.information_target.mode_update == system::ModeUpdate::Ready || self.information_target.mode_update == system::ModeUpdate::Update
+                1 => { device_type = DeviceType::Controller; }
+                2 => { device_type = DeviceType::LinkClient; }
+                3 => { device_type = DeviceType::LinkServer; }
+                4 => { device_type = DeviceType::BleClient; }
+                5 => { device_type = DeviceType::BleServer; }
+                6 => { device_type = DeviceType::Tester; }
+                7 => { device_type = DeviceType::Monitor; }
+                _ => {}
+            }
+
+            self.request(self.device_type_select.unwrap_or(device_type), DataType::Information);
+        }
+
+        if let Data::Information(information) = self.check()
+        {
+            let information = *information;
+            let device_type = self.receiver.get_header().from;
+
+            self.emit(Event::InformationReceived{ device_type, information });
+
+            // 연속 업데이트에서 이미 끝난 장치는 제외
+            if information.model_number != ModelNumber::None && !self.vec_device_result.iter().any(|result| result.device_type == device_type)
+            {
+                if !self.vec_responder.iter().any(|(device_type_responder, _)| *device_type_responder == device_type)
                 {
-                    // 업데이트를 할 수 있는 장치인 경우 다음 단계로 넘어감
-                    if self.find_matched_firmware_file(self.information_target.model_number)
-                    {
-                        self.set_sequence(Sequence::CheckUpdateLocation);
-                    }
-                    else
-                    {
-                        self.set_sequence(Sequence::NoMatchedFirmwareFile);
-                    }
+                    self.vec_responder.push((device_type, information));
                 }
-                else if self.information_target.mode_update == system::ModeUpdate::Complete
+
+                // 업데이트할 장치가 정해지지 않았고 고르거나 순서를 정할 필요도 없으면 처음 응답한 장치를 사용
+                let flag_target = match self.device_type_select
                 {
-                    self.set_sequence(Sequence::UpdateComplete);
-                }
-                else if self.information_target.mode_update == system::ModeUpdate::RunApplication
+                    Some(device_type_select) => device_type_select == device_type,
+                    None => !self.config.flag_select_target && !self.config.flag_chain,
+                };
+
+                if flag_target
                 {
-                    self.set_sequence(Sequence::ApplicationMode);
+                    self.start_target(device_type, information);
+                    return;
                 }
             }
         }
         
-        // 원하는 데이터를 얻지 못하고 시간을 초과하는 경우 새로운 장치 검색 모드로 변경
-        if self.time_sequence_start.elapsed().as_millis() > 1200
+        // 응답을 기다리는 시간이 끝나면 응답한 장치 수에 따라 진행하거나 새로운 장치 검색 모드로 변경
+        if self.time_sequence_start.elapsed().as_millis() > self.get_time_information_timeout_ms()
         {
-            self.create_port_list();
-            self.set_sequence(Sequence::LookUpNewDevice);
+            match (self.device_type_select, self.vec_responder.len())
+            {
+                (None, 0) if !self.vec_device_result.is_empty() => self.finish_chain(),
+                (None, 1) =>
+                {
+                    let (device_type, information) = self.vec_responder[0];
+                    self.start_target(device_type, information);
+                }
+                (None, count) if self.config.flag_chain && count > 0 =>
+                {
+                    let (device_type, information) = *self.vec_responder
+                        .iter()
+                        .min_by_key(|(device_type, _)| LIST_DEVICE_TYPE_CHAIN.iter().position(|d| d == device_type))
+                        .unwrap();
+                    self.start_target(device_type, information);
+                }
+                (None, count) if count > 1 => self.set_sequence(Sequence::SelectTarget),
+                _ => self.restart_lookup(),
+            }
+        }
+    }
+
+
+    // 장치 정보를 기다리는 시간(모든 장치 종류에 한 번씩 요청하고 마지막 응답을 받을 때까지는 기다림)
+    fn get_time_information_timeout_ms(&self) -> u128
+    {
+        let time_probe_ms = (COUNT_DEVICE_TYPE_PROBE + 1) * self.config.retry.time_interval_ms as u128;
+
+        time_probe_ms.max(self.config.retry.time_information_timeout_ms as u128)
+    }
+
+
+    // 연속 업데이트 중 장치 하나가 끝나면 결과를 기록하고 성공한 경우 다음 장치 검색
+    fn chain_sequence(&mut self, sequence: Sequence) -> Sequence
+    {
+        if !self.config.flag_chain || self.flag_chain_finished || !sequence.is_finished() || self.device_type_target == DeviceType::None
+        {
+            return sequence;
+        }
+
+        let result = DeviceResult
+        {
+            device_type: self.device_type_target,
+            model_number: self.information_target.model_number,
+            version: self.information_target.version,
+            sequence,
+        };
+
+        self.trace.event("RESULT", &result);
+        self.emit(Event::DeviceFinished{ result: result.clone() });
+        self.vec_device_result.push(result);
+
+        if sequence.is_error()
+        {
+            return sequence;
+        }
+
+        if sequence == Sequence::UpdateComplete
+        {
+            self.remove_session();
+        }
+
+        self.device_type_select = None;
+        self.message_status = String::from("Looking for the next device");
+
+        Sequence::CheckDeviceInformation
+    }
+
+
+    // 다음 장치가 응답하지 않으면 연속 업데이트 종료
+    fn finish_chain(&mut self)
+    {
+        self.flag_chain_finished = true;
+        self.set_sequence(Sequence::UpdateComplete);
+    }
+
+
+    // 연속 업데이트에서 끝난 장치 목록
+    pub fn get_device_results(&self) -> &[DeviceResult]
+    {
+        &self.vec_device_result
+    }
+
+
+    // 응답한 장치 목록
+    pub fn get_responders(&self) -> &[(DeviceType, Information)]
+    {
+        &self.vec_responder
+    }
+
+
+    // 업데이트할 장치 선택(이후 같은 Updater에서는 이 장치만 업데이트)
+    pub fn select_target(&mut self, device_type: DeviceType)
+    {
+        self.device_type_select = Some(device_type);
+
+        if self.sequence == Sequence::SelectTarget
+        {
+            if let Some((device_type, information)) = self.vec_responder.iter().find(|(d, _)| *d == device_type).copied()
+            {
+                self.start_target(device_type, information);
+            }
+        }
+    }
+
+
+    // 선택한 장치의 업데이트 모드에 따라 다음 단계 결정
+    fn start_target(&mut self, device_type: DeviceType, information: Information)
+    {
+        self.information_target = information;
+        self.device_type_target = device_type;
+        self.device_type_select = Some(device_type);
+
+        if self.information_target.mode_update == system::ModeUpdate::Ready || self.information_target.mode_update == system::ModeUpdate::Update
+        {
+            // 업데이트를 할 수 있는 장치인 경우 다음 단계로 넘어감
+            if self.find_matched_firmware_file(self.information_target.model_number)
+            {
+                let sequence = match self.verify_firmware_file()
+                {
+                    true => self.check_version_policy(),
+                    false => Sequence::FirmwareRejected,
+                };
+                self.set_sequence(sequence);
+            }
+            else
+            {
+                self.set_sequence(Sequence::NoMatchedFirmwareFile);
+            }
+        }
+        else if self.information_target.mode_update == system::ModeUpdate::Complete
+        {
+            self.set_sequence(Sequence::UpdateComplete);
+        }
+        else if self.information_target.mode_update == system::ModeUpdate::RunApplication
+        {
+            if self.config.flag_reboot_bootloader
+            {
+                self.send_jump_to_bootloader();
+                self.set_sequence(Sequence::RebootToBootloader);
+            }
+            else
+            {
+                self.set_sequence(Sequence::ApplicationMode);
+            }
+        }
+    }
+
+
+    // 장치의 펌웨어 버전과 파일의 버전을 비교하여 업데이트 여부 결정
+    // 업데이트가 중단되었던 장치(ModeUpdate::Update)는 버전과 관계없이 업데이트를 계속함
+    fn check_version_policy(&self) -> Sequence
+    {
+        if self.information_target.mode_update != system::ModeUpdate::Ready
+        {
+            return Sequence::CheckUpdateLocation;
+        }
+
+        let version_device = self.information_target.version.to_u32();
+        let version_firmware = self.vec_file[self.index_target_vec_file].header.version.to_u32();
+
+        match version_device.cmp(&version_firmware)
+        {
+            Ordering::Equal if !self.config.flag_force_reflash => Sequence::AlreadyUpToDate,
+            Ordering::Greater if !self.config.flag_allow_downgrade => Sequence::DowngradeRefused,
+            _ => Sequence::CheckUpdateLocation,
         }
     }
 
@@ -479,6 +1159,12 @@ impl Updater
                     self.vec_file[i].header.version.minor,
                     self.vec_file[i].header.version.build);
 
+                self.emit(Event::FirmwareMatched{
+                    model_number,
+                    version_device: self.information_target.version,
+                    version_firmware: self.vec_file[i].header.version,
+                });
+
                 return true;
             }
         }
@@ -487,6 +1173,28 @@ impl Updater
     }
 
 
+    // 선택한 펌웨어 파일의 SHA-256, 서명 확인
+    fn verify_firmware_file(&mut self) -> bool
+    {
+        let verification = verify::verify_firmware(&self.vec_file[self.index_target_vec_file], &self.config);
+        let accepted = verification.is_accepted(self.config.flag_allow_unverified);
+
+        self.message_version = format!("{}   ({})", self.message_version, verification);
+        self.trace.event("VERIFY", &verification.to_string());
+        self.emit(Event::FirmwareVerified{ verification: verification.clone(), accepted });
+        self.verification = Some(verification);
+
+        accepted
+    }
+
+
+    // 선택한 펌웨어 파일의 확인 결과
+    pub fn get_verification(&self) -> Option<&Verification>
+    {
+        self.verification.as_ref()
+    }
+
+
     fn run_check_update_location(&mut self)
     {
         // 주기적으로 information 데이터를 요청
@@ -497,103 +1205,740 @@ impl Updater
 
         if let Data::UpdateLocation(update_location) = &mut self.check()
         {
-            self.update_location = update_location.clone();
+            self.update_location = *update_location;
 
-            if self.flag_show_debug_message
+            self.emit(Event::LocationReceived{ index_block_next: self.update_location.index_block_next });
+
+            if self.check_session()
             {
-                println!("Received UpdateLocation: {:?}", self.update_location);
+                self.set_sequence(Sequence::FirmwareUpdate);
             }
-
-            self.set_sequence(Sequence::FirmwareUpdate);
             return;
         }
         
         // 원하는 데이터를 얻지 못하고 시간을 초과하는 경우 새로운 장치 검색 모드로 변경
-        if self.time_sequence_start.elapsed().as_millis() > 1200
+        if self.time_sequence_start.elapsed().as_millis() > self.config.retry.time_location_timeout_ms as u128
         {
-            self.create_port_list();
-            self.set_sequence(Sequence::LookUpNewDevice);
+            self.restart_lookup();
         }
     }
 
 
-    fn run_firmware_update(&mut self)
+    // 이전에 중단된 업데이트 기록을 확인하고 새로운 기록 저장
+    // 장치가 중간 위치를 알려온 경우 같은 펌웨어 파일로 시작한 업데이트만 이어서 진행
+    fn check_session(&mut self) -> bool
     {
-        if self.flag_transfer_success || self.ticker_transfer.check() 
+        let session = match &self.session
         {
-            if self.flag_transfer_success == false
+            Some(session) => session,
+            None => return true,
+        };
+
+        let hash_firmware = firmware::hash_firmware(&self.vec_file[self.index_target_vec_file]);
+        let index_block_next = self.update_location.index_block_next;
+
+        if index_block_next > 0
+        {
+            if let Some(record) = session.find_interrupted(&self.port_name, self.device_type_target, self.information_target.model_number, index_block_next)
             {
-                self.count_error += 1;
+                // 다른 펌웨어 파일로 시작한 경우
+                if record.firmware_hash != hash_firmware
+                {
+                    self.set_sequence(Sequence::ResumeRefused);
+                    return false;
+                }
+
+                // 기록된 위치가 장치가 알려온 위치보다 앞선 경우
+                if record.index_block > index_block_next
+                {
+                    self.set_sequence(Sequence::ResumeMismatch);
+                    return false;
+                }
+
+                // 포트 이름이 바뀐 경우 넘겨받은 이전 포트의 기록은 지움
+                if record.port != self.port_name
+                {
+                    let _ = session.remove(&record.port, self.device_type_target, self.information_target.model_number);
+                }
+
+                self.message_status = format!("Resuming update from block {} / {}", index_block_next, self.index_block_end);
+                self.emit(Event::Resumed{ index_block_next, index_block_end: self.index_block_end });
             }
+        }
+
+        self.hash_firmware = hash_firmware;
+        self.save_session();
+
+        true
+    }
 
-            self.flag_transfer_success = false;
 
-            if let Some(vec_data) = self.vec_file[self.index_target_vec_file].get_data_block(self.update_location.index_block_next, 2)
+    fn save_session(&mut self)
+    {
+        if let Some(session) = &self.session
+        {
+            let _ = session.save(&SessionRecord
             {
-                self.update.index_block_next = self.update_location.index_block_next;
-                self.update.vec_data = vec_data;
+                port: self.port_name.clone(),
+                device_type: self.device_type_target.into(),
+                model_number: self.information_target.model_number.into(),
+                firmware_hash: self.hash_firmware.clone(),
+                index_block: self.update_location.index_block_next,
+                time_saved: session::get_time_now_ms(),
+            });
+        }
+
+        self.time_session_save = Instant::now();
+    }
+
+
+    fn remove_session(&mut self)
+    {
+        if let Some(session) = &self.session
+        {
+            let _ = session.remove(&self.port_name, self.device_type_target, self.information_target.model_number);
+        }
+    }
+
 
-                self.send(&transfer::transfer(
-                    DataType::Update,
-                    DeviceType::Base,
-                    self.device_type_target,
-                    &self.update.to_vec(),
-                ));
+    // index_block 위치부터 한 패킷을 전송하고 전송한 블럭 수 반환(보낼 데이터가 없으면 None)
+    fn send_update(&mut self, index_block: u16) -> Option<u16>
+    {
+        let vec_data = self.vec_file[self.index_target_vec_file].get_data_block(index_block, self.packet_size.get_count_block())?;
+        let count_block = ((vec_data.len() + 15) >> 4) as u16;
+
+        self.stats.sent(vec_data.len());
+        self.stats.count_block_per_packet = self.packet_size.get_count_block();
+        self.stats.count_window = self.window.get_count_window();
+
+        self.update.index_block_next = index_block;
+        self.update.vec_data = vec_data;
+
+        self.send(&transfer::transfer(
+            DataType::Update,
+            DeviceType::Base,
+            self.device_type_target,
+            &self.update.to_vec(),
+        ));
+
+        Some(count_block)
+    }
+
+
+    fn retry_update(&mut self)
+    {
+        self.count_error += 1;
+        self.stats.retransmitted();
+
+        self.emit(Event::Retry{ index_block_next: self.update_location.index_block_next, count_error: self.count_error });
+
+        if self.packet_size.retried()
+        {
+            self.emit(Event::PacketSizeChanged{ count_block: self.packet_size.get_count_block() });
+        }
+    }
+
+
+    // 장치가 받은 위치부터 다시 전송
+    fn rewind_update(&mut self)
+    {
+        self.retry_update();
+        self.window.gap();
+        self.index_block_send = self.update_location.index_block_next;
+        self.flag_rewound = true;
+    }
+
+
+    // 응답을 기다리지 않고 창 크기만큼 패킷을 이어서 전송
+    fn send_update_window(&mut self)
+    {
+        // 한 주기 동안 장치가 받은 위치가 변하지 않은 경우
+        if self.ticker_transfer.check()
+        {
+            if self.index_block_send > self.update_location.index_block_next && self.index_block_tick == self.update_location.index_block_next
+            {
+                self.rewind_update();
             }
+
+            self.index_block_tick = self.update_location.index_block_next;
+        }
+
+        loop
+        {
+            let count_block = self.packet_size.get_count_block();
+            let count_block_in_flight = self.index_block_send.saturating_sub(self.update_location.index_block_next);
+
+            if count_block_in_flight.div_ceil(count_block) >= self.window.get_count_window()
+            {
+                break;
+            }
+
+            match self.send_update(self.index_block_send)
+            {
+                Some(count_block_sent) => self.index_block_send += count_block_sent,
+                None => break,
+            }
+        }
+    }
+
+
+    fn run_firmware_update(&mut self)
+    {
+        if self.config.packet.count_window > 1
+        {
+            self.send_update_window();
+        }
+        else if self.flag_transfer_success || self.ticker_transfer.check() 
+        {
+            if !self.flag_transfer_success
+            {
+                self.retry_update();
+            }
+
+            self.flag_transfer_success = false;
+
+            self.send_update(self.update_location.index_block_next);
         }
         
         match self.check().clone()
         {
-            Data::UpdateLocation(update_location_new) =>
+            Data::UpdateLocation(update_location_new) if self.update_location.index_block_next != update_location_new.index_block_next =>
             {
-                if self.update.index_block_next != update_location_new.index_block_next
+                self.count_error = 0;
+                self.flag_transfer_success = true;
+                self.flag_rewound = false;
+                self.update_location = update_location_new;
+                self.index_block_send = self.index_block_send.max(self.update_location.index_block_next);
+                self.stats.acknowledged(self.update_location.index_block_next);
+                self.window.acknowledged();
+
+                self.emit(Event::BlockAcknowledged{ index_block_next: self.update_location.index_block_next, index_block_end: self.index_block_end });
+
+                if self.packet_size.acknowledged()
                 {
-                    self.count_error = 0;
-                    self.flag_transfer_success = true;
-                    self.update_location = update_location_new.clone();
+                    self.emit(Event::PacketSizeChanged{ count_block: self.packet_size.get_count_block() });
                 }
             }
 
-            Data::Information(information) =>
+            // 연속 전송 중 장치가 같은 위치를 다시 알려온 경우 중간에 빠진 패킷이 있음
+            Data::UpdateLocation(_) if self.config.packet.count_window > 1 && !self.flag_rewound && self.index_block_send > self.update_location.index_block_next =>
             {
-                if information.mode_update == system::ModeUpdate::Complete
-                {
-                    self.set_sequence(Sequence::UpdateComplete);
-                }
+                self.rewind_update();
+            }
+
+            Data::Information(information) if information.mode_update == system::ModeUpdate::Complete =>
+            {
+                self.set_sequence(Sequence::VerifyUpdate);
             }
 
             _ => {}
         }
 
+        // 진행 위치를 주기적으로 기록(응답이 없는 동안에도 다른 포트에서 진행 중인 기록으로 보도록 시각 갱신)
+        if self.sequence == Sequence::FirmwareUpdate && self.time_session_save.elapsed().as_millis() > 1000
+        {
+            self.save_session();
+        }
+
         // 에러가 일정 이상 쌓이면 오류 처리하고 업데이트 중단
-        if self.count_error > 30
+        if self.count_error > self.config.retry.count_error_max
         {
             self.set_sequence(Sequence::NoAnswer);
         }
 
         // 업데이트 제한 시간 초과
-        if self.time_sequence_start.elapsed().as_millis() > 300000
+        if self.time_sequence_start.elapsed().as_millis() > self.config.retry.time_update_limit_ms as u128
         {
             self.set_sequence(Sequence::UpdateTimeOver);
         }
     }
 
 
-    pub fn get_update_information(&self) -> (i32, i32, i32, f32)
+    // 장치가 다시 시작된 후 정보를 요청하여 펌웨어 파일의 모델 번호, 버전과 비교
+    // 재시작하면서 포트가 사라지는 경우 다시 나타날 때까지 장치 검색
+    fn run_verify_update(&mut self)
     {
-        if let Sequence::FirmwareUpdate = self.sequence
+        if let Some(information) = self.wait_restart(self.config.retry.time_verify_limit_ms, Sequence::VerifyFailed)
         {
-            let time_progress = self.time_sequence_start.elapsed().as_millis() as i32;
-            let time_total = time_progress * self.index_block_end as i32 / self.update.index_block_next as i32;
-            let time_left = time_total - time_progress;
+            // 아직 재시작하지 않은 경우 계속 기다림
+            if information.mode_update != system::ModeUpdate::Complete
+            {
+                self.information_target = information;
+                self.information_verify = Some(information);
 
-            let progress: f32 = self.update_location.index_block_next as f32 * 100.0 / self.index_block_end as f32;
+                self.emit(Event::InformationReceived{ device_type: self.device_type_target, information });
 
-            (time_total, time_progress, time_left, progress)
+                let header = &self.vec_file[self.index_target_vec_file].header;
+
+                if information.model_number == header.model_number && information.version.to_u32() == header.version.to_u32()
+                {
+                    self.set_sequence(Sequence::UpdateComplete);
+                }
+                else
+                {
+                    self.set_sequence(Sequence::VerifyFailed);
+                }
+            }
         }
-        else
+    }
+
+
+    // 부트로더로 다시 시작한 장치가 응답하면 업데이트를 이어서 진행
+    fn run_reboot_to_bootloader(&mut self)
+    {
+        match self.wait_restart(self.config.retry.time_reboot_limit_ms, Sequence::ApplicationMode)
         {
-            (0, 0, 0, 0_f32)
+            // 명령을 받지 못해 아직 애플리케이션 모드인 경우 다시 보냄
+            Some(information) if information.mode_update == system::ModeUpdate::RunApplication =>
+            {
+                self.send_jump_to_bootloader();
+            }
+
+            Some(information) =>
+            {
+                self.emit(Event::InformationReceived{ device_type: self.device_type_target, information });
+                self.start_target(self.device_type_target, information);
+            }
+
+            None => {}
         }
     }
+
+
+    fn send_jump_to_bootloader(&mut self)
+    {
+        self.send(&transfer::command(self.device_type_target, CommandType::JumpToBootloader, 0));
+    }
+
+
+    // 다시 시작하는 장치에 주기적으로 정보를 요청하고 장치가 보낸 정보를 반환
+    // 장치가 재시작하면서 포트가 사라진 경우 다시 열고, 제한 시간을 넘으면 sequence_timeout으로 변경
+    fn wait_restart(&mut self, time_limit_ms: u32, sequence_timeout: Sequence) -> Option<Information>
+    {
+        if self.port.is_none()
+        {
+            if let Some(port_name) = self.next_port_name()
+            {
+                if !self.open_port(port_name)
+                {
+                    self.create_port_list();
+                }
+            }
+        }
+        else if self.ticker_transfer.check()
+        {
+            self.request(self.device_type_target, DataType::Information);
+        }
+
+        if let Data::Information(information) = self.check()
+        {
+            let information = *information;
+
+            if self.receiver.get_header().from == self.device_type_target
+            {
+                return Some(information);
+            }
+        }
+
+        if self.port.is_some() && self.error_io.is_some() && !self.flag_port_external
+        {
+            self.disconnect();
+        }
+
+        if self.time_sequence_start.elapsed().as_millis() > time_limit_ms as u128
+        {
+            self.set_sequence(sequence_timeout);
+        }
+
+        None
+    }
+
+
+    // 장치가 분리되면 상태를 초기화하고 다음 장치 검색
+    // 시리얼 포트는 포트 목록에서 사라지면, 외부 통로는 응답이 없으면 분리된 것으로 판단
+    fn run_wait_for_removal(&mut self)
+    {
+        let flag_tick = self.ticker_transfer.check();
+
+        if flag_tick && self.flag_port_external && self.port.is_some()
+        {
+            self.request(self.device_type_target, DataType::Information);
+        }
+
+        let _ = self.check();
+
+        if self.port.is_some() && self.error_io.is_some()
+        {
+            self.disconnect();
+        }
+
+        let flag_removed = match self.flag_port_external
+        {
+            true => self.port.is_none() || self.time_receive.max(self.time_sequence_start).elapsed().as_millis() > self.config.retry.time_information_timeout_ms as u128,
+            false => flag_tick && !Updater::lookup_all_devices().contains(&self.port_name),
+        };
+
+        if flag_removed
+        {
+            self.reset();
+
+            // 외부 통로가 끊어진 경우 connect로 새 통로를 받을 때까지 기다림
+            match self.port.is_some()
+            {
+                true => self.restart_lookup(),
+                false =>
+                {
+                    self.create_port_list();
+                    self.set_sequence(Sequence::LookUpNewDevice);
+                }
+            }
+        }
+    }
+
+
+    // 다음 장치를 위해 장치별 상태 초기화(성공, 실패 수는 유지)
+    fn reset(&mut self)
+    {
+        self.device_type_target = DeviceType::None;
+        self.information_target = Information::new();
+        self.vec_responder.clear();
+        self.device_type_select = self.config.device_type_target;
+        self.vec_device_result.clear();
+        self.flag_chain_finished = false;
+        self.update_location = UpdateLocation::new();
+        self.index_target_vec_file = 0;
+        self.verification = None;
+        self.index_block_end = 0;
+        self.message_version.clear();
+        self.update = Update::new();
+        self.flag_transfer_success = true;
+        self.count_error = 0;
+        self.error = None;
+        self.error_io = None;
+        self.hash_firmware.clear();
+        self.information_verify = None;
+        self.stats = TransferStats::new();
+    }
+
+
+    // 반복 모드에서 다음 장치를 기다리는 중인지 확인
+    pub fn is_loop_enabled(&self) -> bool
+    {
+        self.config.flag_loop && self.sequence != Sequence::NoFirmwareFile
+    }
+
+
+    // 반복 모드에서 성공, 실패한 장치 수
+    pub fn get_counts(&self) -> (u32, u32)
+    {
+        (self.count_pass, self.count_fail)
+    }
+
+
+    // 현재 패킷당 블럭 수
+    pub fn get_count_block_per_packet(&self) -> u16
+    {
+        self.packet_size.get_count_block()
+    }
+
+
+    // 현재 연속으로 보낼 수 있는 패킷 수
+    pub fn get_count_window(&self) -> u16
+    {
+        self.window.get_count_window()
+    }
+
+
+    // (장치가 다음에 받을 블럭 번호, 전체 블럭 수)
+    pub fn get_update_progress(&self) -> (u16, u16)
+    {
+        (self.update_location.index_block_next, self.index_block_end)
+    }
+
+
+    pub fn get_transfer_stats(&self) -> &TransferStats
+    {
+        &self.stats
+    }
+}
+
+
+impl Default for Updater
+{
+    fn default() -> Self
+    {
+        Updater::new()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::emulator;
+
+    fn run_version_policy(version_device: Version, config: UpdaterConfig) -> (Sequence, usize)
+    {
+        let version_firmware = Version{ major: 22, minor: 2, build: 0 };
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, version_firmware, 16 * 8);
+
+        let (mut updater, mut emulator) = emulator::connect(config, vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP5, version_device, system::ModeUpdate::Ready);
+
+        let sequence = emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10));
+
+        (sequence, emulator.get_image().len())
+    }
+
+    #[test]
+    fn same_version_is_skipped_unless_forced()
+    {
+        let version = Version{ major: 22, minor: 2, build: 0 };
+
+        assert_eq!(run_version_policy(version, emulator::config_unverified()), (Sequence::AlreadyUpToDate, 0));
+
+        let mut config = emulator::config_unverified();
+        config.flag_force_reflash = true;
+        assert_eq!(run_version_policy(version, config), (Sequence::UpdateComplete, 16 * 9));
+    }
+
+    #[test]
+    fn downgrade_is_refused_unless_allowed()
+    {
+        let version = Version{ major: 22, minor: 3, build: 0 };
+
+        assert_eq!(run_version_policy(version, emulator::config_unverified()), (Sequence::DowngradeRefused, 0));
+
+        let mut config = emulator::config_unverified();
+        config.flag_allow_downgrade = true;
+        assert_eq!(run_version_policy(version, config), (Sequence::UpdateComplete, 16 * 9));
+    }
+
+    #[test]
+    fn unverified_firmware_is_rejected()
+    {
+        let version = Version{ major: 22, minor: 1, build: 0 };
+
+        assert_eq!(run_version_policy(version, UpdaterConfig::new()), (Sequence::FirmwareRejected, 0));
+    }
+
+    #[test]
+    fn target_is_chosen_among_responders()
+    {
+        let version = Version{ major: 22, minor: 1, build: 0 };
+        let vec_file = vec![
+            emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 0 }, 16 * 8),
+            emulator::build_firmware(ModelNumber::Drone3ControllerP2, Version{ major: 22, minor: 2, build: 0 }, 16 * 8),
+        ];
+        let vec_expected = vec_file[1].data_array.clone();
+
+        let mut config = emulator::config_unverified();
+        config.flag_select_target = true;
+
+        // 드론이 연결된 조종기
+        let (mut updater, mut emulator) = emulator::connect(config, vec_file, DeviceType::Controller, ModelNumber::Drone3ControllerP2, version, system::ModeUpdate::Ready);
+        emulator.set_bridge(DeviceType::Drone, ModelNumber::Drone3DroneP5, version, system::ModeUpdate::Ready);
+
+        let time_start = Instant::now();
+        while *updater.get_sequence() != Sequence::SelectTarget && time_start.elapsed() < Duration::from_secs(5)
+        {
+            updater.run();
+            emulator.run();
+            std::thread::sleep(Duration::from_micros(200));
+        }
+
+        let vec_device_type: Vec<DeviceType> = updater.get_responders().iter().map(|(device_type, _)| *device_type).collect();
+        assert_eq!(vec_device_type, vec![DeviceType::Controller, DeviceType::Drone]);
+
+        updater.select_target(DeviceType::Controller);
+        assert_eq!(emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10)), Sequence::UpdateComplete);
+        assert_eq!(emulator.get_image(), &vec_expected[..]);
+        assert!(emulator.get_bridge().unwrap().get_image().is_empty());
+    }
+
+    #[test]
+    fn unreadable_firmware_file_is_reported()
+    {
+        let mut config = emulator::config_unverified();
+        config.vec_path_firmware_file = vec![PathBuf::from("/nonexistent/missing.eb")];
+
+        let error = Updater::with_config(config).map(|_| ()).unwrap_err();
+        assert!(error.starts_with("Can't read firmware file /nonexistent/missing.eb"), "{}", error);
+    }
+
+    #[test]
+    fn port_open_failure_is_kept_in_context()
+    {
+        let mut updater = Updater::with_firmware(emulator::config_unverified(), Vec::new());
+
+        assert!(!updater.open_port(String::from("/nonexistent/ttyUSB9")));
+        assert!(updater.get_error_context().error_io.is_some());
+    }
+
+    #[test]
+    fn every_device_type_is_probed()
+    {
+        let version = Version{ major: 22, minor: 1, build: 0 };
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 0 }, 16 * 8);
+
+        let (mut updater, mut emulator) = emulator::connect(emulator::config_unverified(), vec![eb], DeviceType::Monitor, ModelNumber::Drone3DroneP5, version, system::ModeUpdate::Ready);
+
+        assert_eq!(emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10)), Sequence::UpdateComplete);
+        assert_eq!(updater.get_device_type(), DeviceType::Monitor);
+    }
+
+    #[test]
+    fn chain_updates_controller_then_drone()
+    {
+        let version = Version{ major: 22, minor: 1, build: 0 };
+        let vec_file = vec![
+            emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 0 }, 16 * 8),
+            emulator::build_firmware(ModelNumber::Drone3ControllerP2, Version{ major: 22, minor: 2, build: 0 }, 16 * 12),
+        ];
+        let vec_expected_drone = vec_file[0].data_array.clone();
+        let vec_expected_controller = vec_file[1].data_array.clone();
+
+        let mut config = emulator::config_unverified();
+        config.flag_chain = true;
+
+        let (mut updater, mut emulator) = emulator::connect(config, vec_file, DeviceType::Controller, ModelNumber::Drone3ControllerP2, version, system::ModeUpdate::Ready);
+        emulator.set_bridge(DeviceType::Drone, ModelNumber::Drone3DroneP5, version, system::ModeUpdate::Ready);
+
+        assert_eq!(emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(20)), Sequence::UpdateComplete);
+        assert_eq!(emulator.get_image(), &vec_expected_controller[..]);
+        assert_eq!(emulator.get_bridge().unwrap().get_image(), &vec_expected_drone[..]);
+
+        let vec_result: Vec<(DeviceType, Sequence)> = updater.get_device_results().iter().map(|result| (result.device_type, result.sequence)).collect();
+        assert_eq!(vec_result, vec![(DeviceType::Controller, Sequence::UpdateComplete), (DeviceType::Drone, Sequence::UpdateComplete)]);
+    }
+
+    #[test]
+    fn chain_without_answer_keeps_looking()
+    {
+        let version = Version{ major: 22, minor: 1, build: 0 };
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 0 }, 16 * 8);
+
+        let mut config = emulator::config_unverified();
+        config.flag_chain = true;
+
+        // 장치가 응답하지 않음
+        let (mut updater, _emulator) = emulator::connect(config, vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP5, version, system::ModeUpdate::Ready);
+
+        let time_start = Instant::now();
+        while time_start.elapsed() < Duration::from_millis(2500)
+        {
+            updater.run();
+            thread::sleep(Duration::from_micros(200));
+        }
+
+        assert_eq!(*updater.get_sequence(), Sequence::CheckDeviceInformation);
+        assert!(updater.get_device_results().is_empty());
+    }
+
+    #[test]
+    fn loop_waits_for_removal_and_counts_units()
+    {
+        let version = Version{ major: 22, minor: 1, build: 0 };
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 0 }, 16 * 8);
+
+        // 외부 통로가 끊어진 후 시리얼 포트를 열려고 하면 오류가 남음
+        let mut config = emulator::config_unverified();
+        config.flag_loop = true;
+        config.discovery = Discovery::Port(String::from("/nonexistent/ttyUSB9"));
+
+        let (mut updater, mut emulator) = emulator::connect(config, vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP5, version, system::ModeUpdate::Ready);
+
+        assert_eq!(emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10)), Sequence::UpdateComplete);
+        updater.run();
+        assert_eq!(*updater.get_sequence(), Sequence::WaitForRemoval);
+        assert_eq!(updater.get_message_status(), "Update Complete, remove the device");
+
+        // 장치 분리
+        drop(emulator);
+        updater.run();
+        assert_eq!(*updater.get_sequence(), Sequence::LookUpNewDevice);
+        assert_eq!(updater.get_device_type(), DeviceType::None);
+
+        // connect로 새 통로를 받을 때까지 시리얼 포트를 검색하지 않음
+        let time_start = Instant::now();
+        while time_start.elapsed() < Duration::from_millis(500)
+        {
+            updater.run();
+            thread::sleep(Duration::from_micros(200));
+        }
+        assert_eq!(*updater.get_sequence(), Sequence::LookUpNewDevice);
+        assert!(updater.get_error_context().error_io.is_none());
+
+        // 다음 장치는 일치하는 펌웨어가 없음
+        let mut emulator = emulator::attach(&mut updater, DeviceType::Drone, ModelNumber::Drone3DroneP6, version, system::ModeUpdate::Ready);
+
+        assert_eq!(emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10)), Sequence::NoMatchedFirmwareFile);
+        assert_eq!(updater.get_counts(), (1, 1));
+    }
+
+    #[test]
+    fn old_image_after_update_fails_verification()
+    {
+        let version_device = Version{ major: 22, minor: 1, build: 0 };
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 0 }, 16 * 8);
+
+        let (mut updater, mut emulator) = emulator::connect(emulator::config_unverified(), vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP5, version_device, system::ModeUpdate::Ready);
+        emulator.set_boot_old_image(true);
+
+        assert_eq!(emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(10)), Sequence::VerifyFailed);
+
+        match updater.get_error()
+        {
+            Some(UpdateError::VerifyFailed{ version_device: Some(version), .. }) => assert_eq!(version.to_u32(), version_device.to_u32()),
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn adaptive_packet_backs_off_to_device_limit()
+    {
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 0 }, 16 * 200);
+        let vec_expected = eb.data_array.clone();
+
+        let mut config = emulator::config_unverified();
+        config.packet.flag_adaptive = true;
+
+        let (mut updater, mut emulator) = emulator::connect(config, vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 1, build: 0 }, system::ModeUpdate::Ready);
+        emulator.set_count_block_max(4);
+        let rx = updater.subscribe();
+
+        assert_eq!(emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(20)), Sequence::UpdateComplete);
+        assert_eq!(emulator.get_image(), &vec_expected[..]);
+
+        let vec_count_block: Vec<u16> = rx.try_iter()
+            .filter_map(|event| match event { Event::PacketSizeChanged{ count_block } => Some(count_block), _ => None })
+            .collect();
+
+        // 장치가 받을 수 없는 크기까지 늘렸다가 줄임
+        assert_eq!(vec_count_block.iter().max(), Some(&5));
+        assert!(vec_count_block.windows(2).any(|pair| pair[0] == 5 && pair[1] < 5));
+    }
+
+    #[test]
+    fn window_resends_after_lost_packets()
+    {
+        let eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 0 }, 16 * 300);
+        let vec_expected = eb.data_array.clone();
+
+        let mut config = emulator::config_unverified();
+        config.packet.count_window = 4;
+
+        let (mut updater, mut emulator) = emulator::connect(config, vec![eb], DeviceType::Drone, ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 1, build: 0 }, system::ModeUpdate::Ready);
+        emulator.set_count_drop(25);
+        let rx = updater.subscribe();
+
+        assert_eq!(emulator::run_until_finished(&mut updater, &mut emulator, Duration::from_secs(20)), Sequence::UpdateComplete);
+        assert_eq!(emulator.get_image(), &vec_expected[..]);
+        assert!(rx.try_iter().any(|event| matches!(event, Event::Retry{ .. })));
+    }
 }
@@ -0,0 +1,286 @@
+use std::{
+    ffi::OsString,
+    fmt,
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+use e_drone::file::EncryptedBinary;
+
+use crate::bundle;
+use crate::config::UpdaterConfig;
+use crate::firmware;
+
+
+// 펌웨어 파일 옆에 두는 확인용 파일
+//   <펌웨어 파일>.sha256    sha256sum 형식(첫 단어가 16진수 SHA-256 값)
+//   <펌웨어 파일>.sig       파일 전체의 ed25519 서명(64 byte 또는 16진수)
+// 묶음 파일 안의 펌웨어는 manifest.toml의 sha256, signature 값을 사용
+pub const EXTENSION_CHECKSUM: &str = "sha256";
+pub const EXTENSION_SIGNATURE: &str = "sig";
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verification
+{
+    Signed,                 // 공개키로 서명 확인
+    Checksum,               // SHA-256 값 일치
+    Unverified(String),     // 확인할 정보가 없음(이유)
+    Failed(String),         // 손상 또는 변조(이유)
+}
+
+
+impl Verification
+{
+    // 변조된 파일은 허용 여부와 관계없이 사용하지 않음
+    pub fn is_accepted(&self, flag_allow_unverified: bool) -> bool
+    {
+        match self
+        {
+            Verification::Signed | Verification::Checksum => true,
+            Verification::Unverified(_) => flag_allow_unverified,
+            Verification::Failed(_) => false,
+        }
+    }
+}
+
+
+impl fmt::Display for Verification
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Verification::Signed => write!(f, "signature verified"),
+            Verification::Checksum => write!(f, "checksum verified"),
+            Verification::Unverified(reason) => write!(f, "unverified, {}", reason),
+            Verification::Failed(reason) => write!(f, "verification failed, {}", reason),
+        }
+    }
+}
+
+
+// 공개키가 설정되어 있으면 서명을, 없으면 SHA-256 값을 확인
+pub fn verify_firmware(eb: &EncryptedBinary, config: &UpdaterConfig) -> Verification
+{
+    let key = match &config.path_public_key
+    {
+        Some(path) => match read_public_key(path)
+        {
+            Ok(key) => Some(key),
+            Err(e) => return Verification::Failed(e),
+        },
+        None => None,
+    };
+
+    let (sha256, signature) = match read_source(Path::new(&eb.file_name))
+    {
+        Ok(source) => source,
+        Err(e) => return Verification::Failed(e),
+    };
+
+    if let Some(sha256) = &sha256
+    {
+        if !sha256.eq_ignore_ascii_case(&firmware::hash_firmware(eb))
+        {
+            return Verification::Failed(String::from("checksum mismatch"));
+        }
+    }
+
+    match (key, signature)
+    {
+        (Some(key), Some(signature)) => match key.verify(&eb.data_array, &signature)
+        {
+            Ok(_) => Verification::Signed,
+            Err(_) => Verification::Failed(String::from("invalid signature")),
+        },
+        (Some(_), None) => Verification::Unverified(String::from("no signature")),
+        (None, _) if sha256.is_some() => Verification::Checksum,
+        (None, _) => Verification::Unverified(String::from("no checksum")),
+    }
+}
+
+
+// 공개키 파일(32 byte 또는 16진수)
+pub fn read_public_key(path: &Path) -> Result<VerifyingKey, String>
+{
+    let data_array = fs::read(path).map_err(|e| format!("Can't read public key {}: {}", path.display(), e))?;
+
+    let data_array = decode_raw_or_hex(data_array, 32).map_err(|e| format!("Invalid public key {}: {}", path.display(), e))?;
+    let array: [u8; 32] = data_array.try_into().map_err(|_| format!("Invalid public key {}: not 32 bytes", path.display()))?;
+
+    VerifyingKey::from_bytes(&array).map_err(|e| format!("Invalid public key {}: {}", path.display(), e))
+}
+
+
+// 펌웨어 파일의 SHA-256 값과 서명(없으면 None)
+fn read_source(path: &Path) -> Result<(Option<String>, Option<Signature>), String>
+{
+    // 묶음 파일 안의 펌웨어(목록의 파일 이름에 하위 폴더가 있을 수 있으므로 상위 경로를 차례로 확인)
+    if let Some(path_bundle) = path.ancestors().skip(1).find(|path| bundle::is_bundle(path))
+    {
+        if let Ok(path_file) = path.strip_prefix(path_bundle)
+        {
+            let file = path_file
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let entry = bundle::read_manifest_entry(path_bundle, &file)?;
+
+            let signature = match entry.signature
+            {
+                Some(text) => Some(parse_signature(decode_hex(&text)?)?),
+                None => None,
+            };
+
+            return Ok((Some(entry.sha256), signature));
+        }
+    }
+
+    let sha256 = read_sidecar(path, EXTENSION_CHECKSUM)?
+        .and_then(|data_array| String::from_utf8_lossy(&data_array).split_whitespace().next().map(String::from));
+
+    let signature = match read_sidecar(path, EXTENSION_SIGNATURE)?
+    {
+        Some(data_array) => Some(parse_signature(decode_raw_or_hex(data_array, 64)?)?),
+        None => None,
+    };
+
+    Ok((sha256, signature))
+}
+
+
+// 확인용 파일 경로(<펌웨어 파일>.<확장자>)
+pub fn path_sidecar(path: &Path, extension: &str) -> PathBuf
+{
+    let mut name: OsString = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+
+    PathBuf::from(name)
+}
+
+
+fn read_sidecar(path: &Path, extension: &str) -> Result<Option<Vec<u8>>, String>
+{
+    let path = path_sidecar(path, extension);
+
+    match fs::read(&path)
+    {
+        Ok(data_array) => Ok(Some(data_array)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+
+fn parse_signature(data_array: Vec<u8>) -> Result<Signature, String>
+{
+    Signature::from_slice(&data_array).map_err(|_| String::from("invalid signature format"))
+}
+
+
+// 정해진 크기면 그대로, 아니면 16진수 문자열로 해석
+fn decode_raw_or_hex(data_array: Vec<u8>, size: usize) -> Result<Vec<u8>, String>
+{
+    match data_array.len() == size
+    {
+        true => Ok(data_array),
+        false => decode_hex(&String::from_utf8_lossy(&data_array)),
+    }
+}
+
+
+fn decode_hex(text: &str) -> Result<Vec<u8>, String>
+{
+    let text = text.trim();
+
+    if !text.len().is_multiple_of(2)
+    {
+        return Err(String::from("odd number of hex digits"));
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|index| text.get(index..index + 2)
+            .and_then(|digit| u8::from_str_radix(digit, 16).ok())
+            .ok_or_else(|| String::from("invalid hex digit")))
+        .collect()
+}
+
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use e_drone::system::{ModelNumber, Version};
+
+    use crate::emulator;
+
+    #[test]
+    fn signature_and_checksum_are_checked()
+    {
+        let path_dir = std::env::temp_dir().join(format!("drone_updater_verify_{}", std::process::id()));
+        fs::create_dir_all(&path_dir).unwrap();
+
+        let mut eb = emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 0 }, 16 * 8);
+        eb.file_name = path_dir.join("drone.eb").to_string_lossy().to_string();
+        let path = PathBuf::from(&eb.file_name);
+
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let path_key = path_dir.join("public.key");
+        fs::write(&path_key, key.verifying_key().to_bytes()).unwrap();
+
+        let mut config = UpdaterConfig::new();
+        let verification_none = verify_firmware(&eb, &config);
+
+        fs::write(path_sidecar(&path, EXTENSION_CHECKSUM), format!("{}  drone.eb\n", firmware::hash_firmware(&eb))).unwrap();
+        let verification_checksum = verify_firmware(&eb, &config);
+
+        config.path_public_key = Some(path_key);
+        let verification_unsigned = verify_firmware(&eb, &config);
+
+        fs::write(path_sidecar(&path, EXTENSION_SIGNATURE), key.sign(&eb.data_array).to_bytes()).unwrap();
+        let verification_signed = verify_firmware(&eb, &config);
+
+        // 서명 후 파일 내용이 바뀐 경우
+        eb.data_array[40] ^= 0xFF;
+        fs::write(path_sidecar(&path, EXTENSION_CHECKSUM), firmware::hash_firmware(&eb)).unwrap();
+        let verification_tampered = verify_firmware(&eb, &config);
+
+        // 묶음 파일의 하위 폴더에 있는 펌웨어
+        let eb_nested = emulator::build_firmware(ModelNumber::Drone3DroneP5, Version{ major: 22, minor: 2, build: 0 }, 16 * 8);
+        let entry = bundle::ManifestEntry
+        {
+            file: String::from("images/drone.eb"),
+            model_number: String::from("Drone3DroneP5"),
+            version: String::from("22.2.0"),
+            sha256: firmware::hash_firmware(&eb_nested),
+            min_bootloader: None,
+            release_notes: String::new(),
+            signature: None,
+        };
+        let path_bundle = path_dir.join("firmware.tar");
+        emulator::write_bundle(&path_bundle, &[(&entry, &eb_nested.data_array)]);
+        let vec_image = bundle::read_bundle(&path_bundle).unwrap();
+        let verification_bundle = verify_firmware(&vec_image[0].eb, &UpdaterConfig::new());
+        fs::remove_dir_all(&path_dir).unwrap();
+
+        assert_eq!(verification_none, Verification::Unverified(String::from("no checksum")));
+        assert!(verification_none.is_accepted(true) && !verification_none.is_accepted(false));
+        assert_eq!(verification_checksum, Verification::Checksum);
+        assert_eq!(verification_unsigned, Verification::Unverified(String::from("no signature")));
+        assert_eq!(verification_signed, Verification::Signed);
+        assert_eq!(verification_tampered, Verification::Failed(String::from("invalid signature")));
+        assert!(!verification_tampered.is_accepted(true));
+        assert_eq!(verification_bundle, Verification::Checksum);
+    }
+}